@@ -1,4 +1,4 @@
-use jono_core::{Context, Forum, JobStatus, JonoError, current_timestamp_ms, generate_job_id};
+use jono_core::{Context, Forum, JobStatus, current_timestamp_ms, generate_job_id};
 
 pub fn create_test_context() -> Context {
     // job id is random enough for now 🤷
@@ -28,11 +28,7 @@ impl JobFixture {
             JobStatus::Started => keys.started_set(),
             JobStatus::Aborted => keys.aborted_set(),
             JobStatus::Completed => keys.completed_set(),
-            _ => {
-                return Err(JonoError::InvalidJob(
-                    "Cannot directly create this job type".to_string(),
-                ));
-            }
+            JobStatus::Perished => keys.perished_set(),
         };
 
         let mut conn = context.get_connection().await?;
@@ -77,9 +73,14 @@ impl JobFixture {
             .atomic()
             .cmd("DEL").arg(keys.postponed_set())
             .cmd("DEL").arg(keys.queued_set())
+            .cmd("DEL").arg(keys.queued_wake_up_list())
             .cmd("DEL").arg(keys.started_set())
             .cmd("DEL").arg(keys.aborted_set())
             .cmd("DEL").arg(keys.completed_set())
+            .cmd("DEL").arg(keys.harvesting_set())
+            .cmd("DEL").arg(keys.reap_failed_set())
+            .cmd("DEL").arg(keys.perished_set())
+            .cmd("DEL").arg(keys.event_stream())
             .cmd("DEL").arg(keys.job_metadata_hash(&self.job_id))
             .query(&mut conn)?;
 
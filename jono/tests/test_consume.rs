@@ -1,20 +1,14 @@
 #![cfg(all(feature = "produce", feature = "consume"))]
 
 mod common;
+mod workers;
 
 use common::create_test_context;
 use jono::prelude::*;
-use jono_core::Result;
+use jono_core::{Result, current_timestamp_ms};
 use serde_json::json;
 use std::time::Duration;
-
-struct NoopWorker;
-
-impl Worker for NoopWorker {
-    async fn work(&self, _: &Workload) -> Result<WorkSummary> {
-        Ok(WorkSummary::Success(Some(json!({"processed": true}))))
-    }
-}
+use workers::{FailWorker, NoopWorker};
 
 #[tokio::test]
 async fn test_basics() -> Result<()> {
@@ -78,3 +72,1146 @@ async fn test_nonexistent_job() -> Result<()> {
     assert!(consumer.run_next().await.is_ok_and(|v| v.is_none()));
     Ok(())
 }
+
+#[tokio::test]
+async fn test_failure_retries() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "fail_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), FailWorker);
+
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Failure(_))));
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 1);
+
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Failure(_))));
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Perished
+    );
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 2);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retry_policy_postpones_retries() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let retry_policy = RetryPolicy::fixed(Duration::from_millis(200));
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "fail_action"}))
+        .max_attempts(2)
+        .retry_policy(retry_policy.clone())
+        .submit(&producer)
+        .await?;
+    let metadata = inspector.get_job_metadata(&job_id).await?;
+    assert_eq!(metadata.retry_policy, retry_policy);
+
+    let consumer = Consumer::with_context(context.clone(), FailWorker).with_config(
+        ConsumerConfig::new()
+            .poll_timeout(Duration::from_millis(10))
+            .maintenance_interval(Duration::from_millis(50)),
+    );
+
+    let failed_at = current_timestamp_ms();
+    consumer.run_next().await?;
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Postponed
+    );
+    let job_ids = inspector
+        .get_status_to_job_ids(JobFilter::default())
+        .await?;
+    assert_eq!(job_ids.postponed, vec![job_id.clone()]);
+
+    // not due yet
+    assert!(consumer.run_next().await?.is_none());
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(consumer.run_next().await?.is_some());
+    assert!(current_timestamp_ms() - failed_at >= 200);
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Perished
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[test]
+fn test_retry_policy_delays() {
+    let fixed = RetryPolicy::fixed(Duration::from_secs(5));
+    assert_eq!(fixed.delay_after_attempt(1), Duration::from_secs(5));
+    assert_eq!(fixed.delay_after_attempt(7), Duration::from_secs(5));
+
+    let exponential =
+        RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(10), 0.0);
+    assert_eq!(exponential.delay_after_attempt(1), Duration::from_secs(1));
+    assert_eq!(exponential.delay_after_attempt(3), Duration::from_secs(4));
+    assert_eq!(exponential.delay_after_attempt(50), Duration::from_secs(10));
+
+    let jittered = RetryPolicy::exponential(Duration::from_secs(8), Duration::from_secs(8), 0.5);
+    let delay = jittered.delay_after_attempt(1);
+    assert!(delay >= Duration::from_secs(4) && delay <= Duration::from_secs(8));
+
+    let schedule = RetryPolicy::schedule([Duration::from_secs(1), Duration::from_secs(30)]);
+    assert_eq!(schedule.delay_after_attempt(1), Duration::from_secs(1));
+    assert_eq!(schedule.delay_after_attempt(2), Duration::from_secs(30));
+    assert_eq!(schedule.delay_after_attempt(3), Duration::from_secs(30));
+
+    assert_eq!(
+        RetryPolicy::default().delay_after_attempt(1),
+        Duration::ZERO
+    );
+}
+
+#[tokio::test]
+async fn test_resurrect_perished() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "fail_action"}))
+        .submit(&producer)
+        .await?;
+
+    // nothing to resurrect while the job is still alive
+    assert!(!producer.resurrect_job(&job_id).await?);
+
+    let consumer = Consumer::with_context(context.clone(), FailWorker);
+    consumer.run_next().await?;
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Perished
+    );
+    let job_ids = inspector
+        .get_status_to_job_ids(JobFilter::default())
+        .await?;
+    assert_eq!(job_ids.perished, vec![job_id.clone()]);
+
+    assert!(producer.resurrect_job(&job_id).await?);
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 0);
+    let timeline = inspector.get_job_timeline(&job_id).await?;
+    assert_eq!(
+        timeline.last().map(|entry| entry.kind),
+        Some(JobEventKind::Resurrected)
+    );
+
+    // a cleaned job isn't recreated as a partial job in the queue
+    producer.clean_job(&job_id).await?;
+    assert!(matches!(
+        producer.resurrect_job(&job_id).await,
+        Err(JonoError::JobNotFound(_))
+    ));
+    let job_ids = inspector
+        .get_status_to_job_ids(JobFilter::default())
+        .await?;
+    assert!(job_ids.queued.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_postponed_promotion() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let scheduler = Scheduler::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "later_action"}))
+        .priority(7)
+        .postponed_to(current_timestamp_ms() + 200)
+        .submit(&producer)
+        .await?;
+
+    // not due yet
+    assert_eq!(scheduler.promote_due_jobs().await?, 0);
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Postponed
+    );
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let consumer = Consumer::with_context(context.clone(), NoopWorker)
+        .with_config(ConsumerConfig::new().poll_timeout(Duration::from_millis(1)));
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Success(_))));
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Completed
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct SlowWorker;
+
+impl Worker for SlowWorker {
+    async fn work(&self, _: &Workload) -> Result<WorkSummary> {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        Ok(WorkSummary::Success(None))
+    }
+}
+
+#[tokio::test]
+async fn test_heartbeat() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "slow_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), SlowWorker).with_config(
+        ConsumerConfig::new()
+            .heartbeat_interval(Duration::from_millis(50))
+            .heartbeat_timeout(Duration::from_millis(100)),
+    );
+
+    let check_heartbeats = async {
+        let mut conn = context.get_connection().await?;
+        let started_set = context.keys().started_set();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let first: i64 = conn.zscore(&started_set, &job_id).await?;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let second: i64 = conn.zscore(&started_set, &job_id).await?;
+
+        // a job running longer than the heartbeat timeout is still alive
+        assert!(second > first);
+        assert!(second > current_timestamp_ms());
+        Ok::<_, JonoError>(())
+    };
+
+    let (summary, checked) = tokio::join!(consumer.run_next(), check_heartbeats);
+    checked?;
+    assert!(matches!(summary?, Some(WorkSummary::Success(_))));
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Completed
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recovered_job_is_not_completed() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let sweeper = Sweeper::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "slow_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    // the heartbeat expires long before the next beat
+    let consumer = Consumer::with_context(context.clone(), SlowWorker).with_config(
+        ConsumerConfig::new()
+            .heartbeat_interval(Duration::from_secs(5))
+            .heartbeat_timeout(Duration::from_millis(50)),
+    );
+    let sweep = async {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        sweeper.sweep_expired_jobs().await
+    };
+
+    let (summary, swept) = tokio::join!(consumer.run_next(), sweep);
+    assert_eq!(swept?, 1);
+    assert!(matches!(summary?, Some(WorkSummary::Success(_))));
+
+    // the late finish of the original run doesn't complete the requeued job
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    let mut conn = context.get_connection().await?;
+    let completed: Option<f64> = conn.zscore(context.keys().completed_set(), &job_id).await?;
+    assert_eq!(completed, None);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unreadable_job_is_not_released() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "unreadable_action"}))
+        .submit(&producer)
+        .await?;
+    let mut conn = context.get_connection().await?;
+    let _: () = conn
+        .hset(context.keys().job_metadata_hash(&job_id), "payload", "{")
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), NoopWorker)
+        .with_config(ConsumerConfig::new().poll_timeout(Duration::from_millis(50)));
+    assert!(consumer.run_next().await.is_err());
+
+    // the failed claim isn't tracked, so shutting down leaves the job for the sweeper
+    consumer.run_until(async {}).await?;
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Started);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+/// Reports the job status and attempt count after the other run of the job has finished
+struct LateStatusWorker {
+    inspector: Inspector,
+}
+
+impl Worker for LateStatusWorker {
+    async fn work(&self, load: &Workload) -> Result<WorkSummary> {
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let metadata = self.inspector.get_job_metadata(&load.job_id).await?;
+        Ok(WorkSummary::Success(Some(json!({
+            "status": metadata.status,
+            "attempt_count": metadata.attempt_count,
+        }))))
+    }
+}
+
+#[tokio::test]
+async fn test_late_finish_leaves_reclaimed_job_alone() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let sweeper = Sweeper::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "slow_action"}))
+        .max_attempts(3)
+        .submit(&producer)
+        .await?;
+
+    // the first run stalls past its heartbeat timeout and another consumer claims the job
+    let stalled = Consumer::with_context(context.clone(), SlowWorker).with_config(
+        ConsumerConfig::new()
+            .heartbeat_interval(Duration::from_secs(5))
+            .heartbeat_timeout(Duration::from_millis(50)),
+    );
+    let worker = LateStatusWorker {
+        inspector: Inspector::with_context(context.clone()),
+    };
+    let reclaiming = Consumer::with_context(context.clone(), worker)
+        .with_config(ConsumerConfig::new().heartbeat_interval(Duration::from_millis(50)));
+    let reclaim = async {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        sweeper.sweep_expired_jobs().await?;
+        reclaiming.run_next().await
+    };
+
+    let (stalled_summary, reclaimed_summary) = tokio::join!(stalled.run_next(), reclaim);
+    assert!(matches!(stalled_summary?, Some(WorkSummary::Success(_))));
+
+    // the late finish of the first run didn't resolve the second run
+    let Some(WorkSummary::Success(Some(seen))) = reclaimed_summary? else {
+        panic!("Expected the second run to succeed");
+    };
+    assert_eq!(seen, json!({"status": "Started", "attempt_count": 1}));
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Completed
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct StatusWorker {
+    inspector: Inspector,
+}
+
+impl Worker for StatusWorker {
+    async fn work(&self, load: &Workload) -> Result<WorkSummary> {
+        let status = self.inspector.get_job_status(&load.job_id).await?;
+        Ok(WorkSummary::Success(Some(json!({"status": status}))))
+    }
+}
+
+#[tokio::test]
+async fn test_claimed_job_is_started() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "claim_action"}))
+        .submit(&producer)
+        .await?;
+
+    let worker = StatusWorker {
+        inspector: Inspector::with_context(context.clone()),
+    };
+    let consumer = Consumer::with_context(context.clone(), worker);
+    consumer.run_next().await?;
+
+    let metadata = inspector.get_job_metadata(&job_id).await?;
+    assert_eq!(metadata.work_summary.unwrap(), json!({"status": "Started"}));
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_waiting_consumer_wakes_up_on_submit() -> Result<()> {
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let consumer = Consumer::with_context(context.clone(), NoopWorker).with_config(
+        ConsumerConfig::new()
+            .poll_timeout(Duration::from_secs(5))
+            .maintenance_interval(Duration::from_secs(60)),
+    );
+
+    // the consumer blocks on the empty queue and gets the job as soon as it's submitted
+    let started = std::time::Instant::now();
+    let (summary, job_id) = tokio::join!(consumer.run_next(), async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        JobPlan::new()
+            .payload(json!({"action": "wake_up_action"}))
+            .submit(&producer)
+            .await
+    });
+    let job_id = job_id?;
+    assert!(matches!(summary?, Some(WorkSummary::Success(_))));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrency() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+
+    let mut job_ids = Vec::new();
+    for i in 0..4 {
+        let job_id = JobPlan::new()
+            .payload(json!({"action": "slow_action", "index": i}))
+            .submit(&producer)
+            .await?;
+        job_ids.push(job_id);
+    }
+
+    let consumer = Consumer::with_context(context.clone(), SlowWorker).with_config(
+        ConsumerConfig::new()
+            .concurrency(4)
+            .poll_timeout(Duration::from_millis(10)),
+    );
+
+    // one at a time, four 300 ms jobs would take well over the time limit
+    let run = tokio::time::timeout(Duration::from_millis(800), consumer.run()).await;
+    assert!(run.is_err(), "run should only stop by the timeout");
+
+    for job_id in &job_ids {
+        assert_eq!(
+            inspector.get_job_status(job_id).await?,
+            JobStatus::Completed
+        );
+        producer.clean_job(job_id).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_finishes_in_flight() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "slow_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), SlowWorker)
+        .with_config(ConsumerConfig::new().poll_timeout(Duration::from_millis(10)));
+    consumer
+        .run_until(tokio::time::sleep(Duration::from_millis(100)))
+        .await?;
+
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Completed
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_releases_unfinished() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "slow_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), SlowWorker).with_config(
+        ConsumerConfig::new()
+            .poll_timeout(Duration::from_millis(10))
+            .shutdown_timeout(Duration::from_millis(50)),
+    );
+    consumer
+        .run_until(tokio::time::sleep(Duration::from_millis(50)))
+        .await?;
+
+    // the job didn't finish in time, so it's back in the queue without a used attempt
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 0);
+    let timeline = inspector.get_job_timeline(&job_id).await?;
+    assert_eq!(
+        timeline.last().map(|entry| entry.kind),
+        Some(JobEventKind::Released)
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_run_does_not_block_runtime() -> Result<()> {
+    let context = create_test_context();
+    let consumer = Consumer::with_context(context, NoopWorker).with_config(
+        ConsumerConfig::new()
+            .poll_timeout(Duration::from_millis(1))
+            .poll_interval(Duration::from_secs(2)),
+    );
+
+    // on the current-thread test runtime, a blocking sleep would delay the timeout
+    let started = std::time::Instant::now();
+    let run = tokio::time::timeout(Duration::from_millis(100), consumer.run()).await;
+    assert!(run.is_err());
+    assert!(started.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
+
+struct AbortAwareWorker;
+
+impl Worker for AbortAwareWorker {
+    async fn work(&self, load: &Workload) -> Result<WorkSummary> {
+        load.abort_signal().aborted().await;
+        assert!(load.is_aborted());
+        Ok(WorkSummary::Failure("Stopped on abort".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_abort_running_job() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "abort_aware_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), AbortAwareWorker)
+        .with_config(ConsumerConfig::new().heartbeat_interval(Duration::from_millis(20)));
+    let (summary, aborted) = tokio::join!(consumer.run_next(), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        producer.abort_job(&job_id, 5_000).await
+    });
+    assert!(aborted?);
+    assert!(matches!(summary?, Some(WorkSummary::Failure(_))));
+
+    // the worker gave up on its own; the failure doesn't send the job back to the queue
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Aborted);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct StubbornWorker;
+
+impl Worker for StubbornWorker {
+    async fn work(&self, _: &Workload) -> Result<WorkSummary> {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(WorkSummary::Success(None))
+    }
+}
+
+#[tokio::test]
+async fn test_abort_after_grace_period() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "stubborn_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), StubbornWorker)
+        .with_config(ConsumerConfig::new().heartbeat_interval(Duration::from_millis(20)));
+    let started = std::time::Instant::now();
+    let (summary, aborted) = tokio::join!(consumer.run_next(), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        producer.abort_job(&job_id, 100).await
+    });
+    assert!(aborted?);
+    assert!(matches!(summary?, Some(WorkSummary::Failure(_))));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Aborted);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct RetryWorker;
+
+impl Worker for RetryWorker {
+    async fn work(&self, _: &Workload) -> Result<WorkSummary> {
+        Ok(WorkSummary::Retry(Duration::from_millis(100)))
+    }
+}
+
+#[tokio::test]
+async fn test_retry_without_attempt() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "retry_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), RetryWorker).with_config(
+        ConsumerConfig::new()
+            .poll_timeout(Duration::from_millis(10))
+            .maintenance_interval(Duration::from_millis(50)),
+    );
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Retry(_))));
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Postponed
+    );
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 0);
+
+    // retrying doesn't run out of attempts
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(consumer.run_next().await?.is_some());
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Postponed
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct SnoozeWorker {
+    until: i64,
+}
+
+impl Worker for SnoozeWorker {
+    async fn work(&self, _: &Workload) -> Result<WorkSummary> {
+        Ok(WorkSummary::Snooze(self.until))
+    }
+}
+
+#[tokio::test]
+async fn test_snooze() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "snooze_action"}))
+        .submit(&producer)
+        .await?;
+
+    let until = current_timestamp_ms() + 60_000;
+    let consumer = Consumer::with_context(context.clone(), SnoozeWorker { until });
+    consumer.run_next().await?;
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Postponed
+    );
+
+    let mut conn = context.get_connection().await?;
+    let score: Option<i64> = conn.zscore(context.keys().postponed_set(), &job_id).await?;
+    assert_eq!(score, Some(until));
+
+    // snoozing to the past puts the job straight back to the queue
+    producer.clean_job(&job_id).await?;
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "snooze_action"}))
+        .submit(&producer)
+        .await?;
+    let consumer = Consumer::with_context(context.clone(), SnoozeWorker { until: 0 });
+    consumer.run_next().await?;
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 0);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct DiscardWorker;
+
+impl Worker for DiscardWorker {
+    async fn work(&self, _: &Workload) -> Result<WorkSummary> {
+        Ok(WorkSummary::Discard("Malformed payload".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_discard() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "poison_action"}))
+        .max_attempts(3)
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), DiscardWorker);
+    consumer.run_next().await?;
+
+    // straight to the dead-letter set even with attempts left
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Perished
+    );
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 1);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_timeout() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "stubborn_action"}))
+        .max_attempts(2)
+        .timeout(Duration::from_millis(100))
+        .submit(&producer)
+        .await?;
+    let metadata = inspector.get_job_metadata(&job_id).await?;
+    assert_eq!(metadata.timeout, Some(Duration::from_millis(100)));
+
+    // the job timeout takes precedence over the consumer default
+    let consumer = Consumer::with_context(context.clone(), StubbornWorker)
+        .with_config(ConsumerConfig::new().job_timeout(Duration::from_secs(60)));
+    let started = std::time::Instant::now();
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Failure(_))));
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 1);
+    let mut conn = context.get_connection().await?;
+    let history: String = conn
+        .hget(context.keys().job_metadata_hash(&job_id), "attempt_history")
+        .await?;
+    assert!(history.contains("timeout"));
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_job_timeout() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "stubborn_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), StubbornWorker)
+        .with_config(ConsumerConfig::new().job_timeout(Duration::from_millis(100)));
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Failure(_))));
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Perished
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct PanicWorker;
+
+impl Worker for PanicWorker {
+    async fn work(&self, load: &Workload) -> Result<WorkSummary> {
+        panic!("Worker exploded on {}", load.job_id);
+    }
+}
+
+#[tokio::test]
+async fn test_worker_panic() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "panic_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), PanicWorker);
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Failure(_))));
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 1);
+
+    let mut conn = context.get_connection().await?;
+    let history: String = conn
+        .hget(context.keys().job_metadata_hash(&job_id), "attempt_history")
+        .await?;
+    assert!(history.contains("panic"));
+    assert!(history.contains("Worker exploded"));
+
+    // the same consumer keeps going after the panic
+    assert!(consumer.run_next().await?.is_some());
+    assert_eq!(
+        inspector.get_job_status(&job_id).await?,
+        JobStatus::Perished
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct ErrorWorker;
+
+impl Worker for ErrorWorker {
+    async fn work(&self, load: &Workload) -> Result<WorkSummary> {
+        Err(JonoError::InvalidJob(format!(
+            "Worker choked on {}",
+            load.job_id
+        )))
+    }
+}
+
+#[tokio::test]
+async fn test_worker_error() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "error_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    // the error fails the attempt instead of leaving the job started for the sweeper
+    let consumer = Consumer::with_context(context.clone(), ErrorWorker);
+    let summary = consumer.run_next().await?;
+    assert!(matches!(summary, Some(WorkSummary::Failure(_))));
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.attempt_count, 1);
+
+    let mut conn = context.get_connection().await?;
+    let history: String = conn
+        .hget(context.keys().job_metadata_hash(&job_id), "attempt_history")
+        .await?;
+    assert!(history.contains("\"error\""));
+    assert!(history.contains("Worker choked"));
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+type SeenWorkloads = std::sync::Arc<std::sync::Mutex<Vec<(u32, bool, String, i64, i64, usize)>>>;
+
+struct RecordingWorker {
+    seen: SeenWorkloads,
+}
+
+impl Worker for RecordingWorker {
+    async fn work(&self, load: &Workload) -> Result<WorkSummary> {
+        self.seen.lock().unwrap().push((
+            load.attempt,
+            load.is_last_attempt(),
+            load.origin.clone(),
+            load.initial_priority,
+            load.created_at,
+            load.attempt_history.len(),
+        ));
+        Ok(WorkSummary::Failure("Recorded".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_workload_context() -> Result<()> {
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let submitted_at = current_timestamp_ms();
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "record_action"}))
+        .max_attempts(2)
+        .priority(5)
+        .origin("test-origin")
+        .submit(&producer)
+        .await?;
+
+    let seen = SeenWorkloads::default();
+    let worker = RecordingWorker { seen: seen.clone() };
+    let consumer = Consumer::with_context(context.clone(), worker);
+    consumer.run_next().await?;
+    consumer.run_next().await?;
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(seen.len(), 2);
+    let (attempt, is_last, origin, priority, created_at, history_len) = seen[0].clone();
+    assert_eq!((attempt, is_last, history_len), (1, false, 0));
+    assert_eq!(origin, "test-origin");
+    assert_eq!(priority, 5);
+    assert!(created_at >= submitted_at);
+    let (attempt, is_last, _, _, _, history_len) = seen[1].clone();
+    assert_eq!((attempt, is_last, history_len), (2, true, 1));
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retention() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let keys = context.keys();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let consumer = Consumer::with_context(context.clone(), NoopWorker).with_config(
+        ConsumerConfig::new().retention(Retention::expire(Duration::from_secs(7 * 24 * 60 * 60))),
+    );
+    let mut conn = context.get_connection().await?;
+
+    // the consumer default
+    let kept_id = JobPlan::new()
+        .payload(json!({"action": "audit_action"}))
+        .submit(&producer)
+        .await?;
+    consumer.run_next().await?;
+    let ttl_ms: i64 = conn.pttl(keys.job_metadata_hash(&kept_id)).await?;
+    assert!(ttl_ms > 6 * 24 * 60 * 60 * 1000);
+
+    // fire-and-forget
+    let discarded_id = JobPlan::new()
+        .payload(json!({"action": "fire_and_forget_action"}))
+        .retention(Retention::Discard)
+        .submit(&producer)
+        .await?;
+    consumer.run_next().await?;
+    assert!(!inspector.job_exists(&discarded_id).await?);
+    let completed: Option<f64> = conn.zscore(keys.completed_set(), &discarded_id).await?;
+    assert_eq!(completed, None);
+
+    // never expires
+    let forever_id = JobPlan::new()
+        .payload(json!({"action": "forever_action"}))
+        .retention(Retention::Forever)
+        .submit(&producer)
+        .await?;
+    consumer.run_next().await?;
+    let ttl_ms: i64 = conn.pttl(keys.job_metadata_hash(&forever_id)).await?;
+    assert_eq!(ttl_ms, -1);
+    let completed: Option<f64> = conn.zscore(keys.completed_set(), &forever_id).await?;
+    assert_eq!(completed, Some(f64::INFINITY));
+    assert_eq!(
+        inspector.get_job_status(&forever_id).await?,
+        JobStatus::Completed
+    );
+
+    producer.clean_job(&kept_id).await?;
+    producer.clean_job(&forever_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_handle_wait() -> Result<()> {
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let handle = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit_for_handle(&producer)
+        .await?;
+
+    // nobody is processing the job yet
+    let waited = handle.wait(Some(Duration::from_millis(50))).await;
+    assert!(matches!(waited, Err(JonoError::Timeout(_))));
+
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+    let other_handle = producer.get_job_handle(handle.job_id());
+    let (outcome, other_outcome, _) = tokio::join!(
+        handle.wait(Some(Duration::from_secs(5))),
+        other_handle.wait(None),
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            consumer.run_next().await
+        }
+    );
+    let completed = JobOutcome::Completed(Some(json!({"processed": true})));
+    assert_eq!(outcome?, completed);
+    assert_eq!(other_outcome?, completed);
+
+    // the outcome stays available after the job has finished
+    assert_eq!(
+        handle.wait(Some(Duration::from_millis(50))).await?,
+        completed
+    );
+
+    producer.clean_job(handle.job_id()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_handle_wait_perished() -> Result<()> {
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let handle = JobPlan::new()
+        .payload(json!({"action": "fail_action"}))
+        .submit_for_handle(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), FailWorker);
+    let (outcome, _) = tokio::join!(handle.wait(Some(Duration::from_secs(5))), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        consumer.run_next().await
+    });
+    assert_eq!(outcome?, JobOutcome::Perished("nope".to_string()));
+
+    // a resurrected job is waited on again instead of returning the earlier outcome
+    assert!(producer.resurrect_job(handle.job_id()).await?);
+    let waited = handle.wait(Some(Duration::from_millis(50))).await;
+    assert!(matches!(waited, Err(JonoError::Timeout(_))));
+
+    producer.clean_job(handle.job_id()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_timeline() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), FailWorker);
+    consumer.run_next().await?;
+    consumer.run_next().await?;
+
+    let timeline = inspector.get_job_timeline(&job_id).await?;
+    let kinds: Vec<JobEventKind> = timeline.iter().map(|entry| entry.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            JobEventKind::Submitted,
+            JobEventKind::Started,
+            JobEventKind::Failed,
+            JobEventKind::Retried,
+            JobEventKind::Started,
+            JobEventKind::Failed,
+            JobEventKind::Perished,
+        ]
+    );
+    assert_eq!(timeline[2].detail.as_deref(), Some("nope"));
+    assert!(
+        timeline[1]
+            .worker
+            .ends_with(&format!(":{}", std::process::id()))
+    );
+    assert!(
+        timeline
+            .windows(2)
+            .all(|w| w[0].occurred_at <= w[1].occurred_at)
+    );
+
+    producer.clean_job(&job_id).await?;
+    assert!(matches!(
+        inspector.get_job_timeline(&job_id).await,
+        Err(JonoError::JobNotFound(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_timeline_expires_with_metadata() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let keys = context.keys();
+    let producer = Producer::with_context(context.clone());
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+
+    let expiring_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .retention(Retention::expire(Duration::from_secs(60)))
+        .submit(&producer)
+        .await?;
+    consumer.run_next().await?;
+
+    let discarded_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .retention(Retention::Discard)
+        .submit(&producer)
+        .await?;
+    consumer.run_next().await?;
+
+    let mut conn = context.get_connection().await?;
+    let timeline_ttl: i64 = conn.pttl(keys.job_timeline_list(&expiring_id)).await?;
+    let metadata_ttl: i64 = conn.pttl(keys.job_metadata_hash(&expiring_id)).await?;
+    assert!(timeline_ttl > 0 && timeline_ttl <= 60_000);
+    assert!((metadata_ttl - timeline_ttl).abs() < 1_000);
+
+    let discarded_timeline: bool = conn.exists(keys.job_timeline_list(&discarded_id)).await?;
+    assert!(!discarded_timeline);
+
+    // the outcome goes with the metadata too; a discarded job only wakes up current waiters
+    let outcome_ttl: i64 = conn.pttl(keys.job_outcome_list(&expiring_id)).await?;
+    assert!((metadata_ttl - outcome_ttl).abs() < 1_000);
+    let discarded_outcome_ttl: i64 = conn.pttl(keys.job_outcome_list(&discarded_id)).await?;
+    assert!(discarded_outcome_ttl <= 1_000);
+
+    producer.clean_job(&expiring_id).await?;
+    producer.clean_job(&discarded_id).await?;
+    Ok(())
+}
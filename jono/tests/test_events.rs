@@ -0,0 +1,127 @@
+#![cfg(all(feature = "produce", feature = "consume", feature = "harvest"))]
+
+mod common;
+mod reapers;
+mod workers;
+
+use common::create_test_context;
+use futures_util::{Stream, StreamExt};
+use jono::prelude::*;
+use jono_core::Result;
+use reapers::NoopReaper;
+use serde_json::json;
+use std::time::Duration;
+use workers::{FailWorker, NoopWorker};
+
+async fn next_kinds(
+    events: impl Stream<Item = Result<JobEvent>>,
+    job_id: &str,
+    count: usize,
+) -> Result<Vec<JobEventKind>> {
+    let mut events = std::pin::pin!(events);
+    let mut kinds = Vec::with_capacity(count);
+    while kinds.len() < count {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .expect("Timed out waiting for job events")
+            .expect("Event stream ended")?;
+        assert_eq!(event.job_id, job_id);
+        kinds.push(event.kind);
+    }
+    Ok(kinds)
+}
+
+#[tokio::test]
+async fn test_completed_lifecycle() -> Result<()> {
+    let context = create_test_context();
+    let events = JobEvent::subscribe(&context).await?;
+
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+    consumer.run_next().await?;
+
+    let harvester = Harvester::with_context(context.clone(), NoopReaper)
+        .with_config(HarvestConfig::new().poll_timeout(Duration::from_millis(1)));
+    assert_eq!(harvester.reap_next_batch().await?.len(), 1);
+
+    let kinds = next_kinds(events, &job_id, 4).await?;
+    assert_eq!(
+        kinds,
+        vec![
+            JobEventKind::Submitted,
+            JobEventKind::Started,
+            JobEventKind::Completed,
+            JobEventKind::Harvested,
+        ]
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_lifecycle() -> Result<()> {
+    let context = create_test_context();
+    let events = JobEvent::subscribe(&context).await?;
+
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .max_attempts(2)
+        .submit(&producer)
+        .await?;
+
+    let consumer = Consumer::with_context(context.clone(), FailWorker);
+    consumer.run_next().await?;
+    consumer.run_next().await?;
+
+    let kinds = next_kinds(events, &job_id, 7).await?;
+    assert_eq!(
+        kinds,
+        vec![
+            JobEventKind::Submitted,
+            JobEventKind::Started,
+            JobEventKind::Failed,
+            JobEventKind::Retried,
+            JobEventKind::Started,
+            JobEventKind::Failed,
+            JobEventKind::Perished,
+        ]
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscribe_after() -> Result<()> {
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit(&producer)
+        .await?;
+    producer.abort_job(&job_id, 0).await?;
+
+    // events published before subscribing are replayed from the start of the stream
+    let events = JobEvent::subscribe_after(context.clone(), "0-0");
+    let mut events = std::pin::pin!(events);
+    let submitted = events.next().await.unwrap()?;
+    assert_eq!(submitted.kind, JobEventKind::Submitted);
+    assert_eq!(submitted.topic, context.topic());
+    let aborted = events.next().await.unwrap()?;
+    assert_eq!(aborted.kind, JobEventKind::Aborted);
+
+    // and resuming after the last seen event only gets what came after it
+    let events = JobEvent::subscribe_after(context.clone(), &submitted.event_id);
+    let kinds = next_kinds(events, &job_id, 1).await?;
+    assert_eq!(kinds, vec![JobEventKind::Aborted]);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
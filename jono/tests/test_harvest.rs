@@ -6,7 +6,7 @@ mod workers;
 
 use common::create_test_context;
 use jono::prelude::*;
-use jono_core::Result;
+use jono_core::{Context, Result};
 use reapers::NoopReaper;
 use serde_json::json;
 use std::time::Duration;
@@ -89,3 +89,261 @@ async fn test_clean_harvest() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_reap_until_shutdown() -> Result<()> {
+    let context = create_test_context();
+    let harvester = Harvester::with_context(context.clone(), NoopReaper)
+        .with_config(HarvestConfig::new().poll_timeout(Duration::from_millis(1)));
+    harvester
+        .reap_until(tokio::time::sleep(Duration::from_millis(50)))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reap_does_not_block_runtime() -> Result<()> {
+    let context = create_test_context();
+    let harvester = Harvester::with_context(context.clone(), NoopReaper).with_config(
+        HarvestConfig::new()
+            .poll_timeout(Duration::from_millis(1))
+            .poll_interval(Duration::from_secs(2)),
+    );
+
+    // on the current-thread test runtime, a blocking sleep would delay the timeout
+    let started = std::time::Instant::now();
+    let reaped = tokio::time::timeout(Duration::from_millis(100), harvester.reap()).await;
+    assert!(reaped.is_err());
+    assert!(started.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
+
+struct ErrorReaper;
+
+impl Reaper for ErrorReaper {
+    async fn reap(&self, _: &Reapload) -> Result<ReapSummary> {
+        Err(JonoError::Redis(redis::RedisError::from((
+            redis::ErrorKind::IoError,
+            "Downstream unavailable",
+        ))))
+    }
+}
+
+#[tokio::test]
+async fn test_reap_error_keeps_results() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit(&producer)
+        .await?;
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+    consumer.run_next().await?;
+
+    let config = || {
+        HarvestConfig::new()
+            .poll_timeout(Duration::from_millis(1))
+            .lease_timeout(Duration::from_millis(100))
+    };
+    let failing = Harvester::with_context(context.clone(), ErrorReaper).with_config(config());
+    assert!(failing.reap_next_batch().await.is_err());
+
+    // leased to the failed harvester until the lease expires
+    let harvester = Harvester::with_context(context.clone(), NoopReaper).with_config(config());
+    assert_eq!(harvester.reap_next_batch().await?.len(), 0);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(harvester.reap_next_batch().await?.len(), 1);
+
+    // acknowledged after a successful reap, so it's not harvested again
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(harvester.reap_next_batch().await?.len(), 0);
+    let mut conn = context.get_connection().await?;
+    let harvesting: usize = conn.zcard(context.keys().harvesting_set()).await?;
+    assert_eq!(harvesting, 0);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+struct FailureReaper;
+
+impl Reaper for FailureReaper {
+    async fn reap(&self, _: &Reapload) -> Result<ReapSummary> {
+        Ok(ReapSummary::Failure(
+            "Webhook rejected the result".to_string(),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_reap_failure_dead_letter() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit(&producer)
+        .await?;
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+    consumer.run_next().await?;
+
+    let harvester = Harvester::with_context(context.clone(), FailureReaper).with_config(
+        HarvestConfig::new()
+            .poll_timeout(Duration::from_millis(1))
+            .max_reap_attempts(2),
+    );
+
+    // the first failure is retried right away
+    let summaries = harvester.reap_next_batch().await?;
+    assert!(matches!(summaries.as_slice(), [ReapSummary::Failure(_)]));
+    assert!(harvester.get_reap_failed_job_ids().await?.is_empty());
+
+    let summaries = harvester.reap_next_batch().await?;
+    assert!(matches!(summaries.as_slice(), [ReapSummary::Failure(_)]));
+    assert_eq!(
+        harvester.get_reap_failed_job_ids().await?,
+        vec![job_id.clone()]
+    );
+    assert_eq!(harvester.reap_next_batch().await?.len(), 0);
+
+    let metadata = inspector.get_job_metadata(&job_id).await?;
+    assert_eq!(metadata.reap_attempt_count, 2);
+    assert_eq!(metadata.reap_history.len(), 2);
+    assert_eq!(metadata.reap_history[1].reason, "reap_failure");
+    assert_eq!(
+        metadata.reap_history[1].error,
+        "Webhook rejected the result"
+    );
+
+    // dead-lettered jobs don't expire while waiting for a replay
+    let keys = context.keys();
+    let mut conn = context.get_connection().await?;
+    let metadata_ttl: i64 = conn.pttl(keys.job_metadata_hash(&job_id)).await?;
+    let timeline_ttl: i64 = conn.pttl(keys.job_timeline_list(&job_id)).await?;
+    assert_eq!((metadata_ttl, timeline_ttl), (-1, -1));
+
+    // replayed jobs are harvestable again
+    assert!(harvester.replay_reap_failed(&job_id).await?);
+    let metadata_ttl: i64 = conn.pttl(keys.job_metadata_hash(&job_id)).await?;
+    let timeline_ttl: i64 = conn.pttl(keys.job_timeline_list(&job_id)).await?;
+    assert!(metadata_ttl > 0);
+    assert!((metadata_ttl - timeline_ttl).abs() < 1_000);
+    assert!(!harvester.replay_reap_failed(&job_id).await?);
+    let harvester = Harvester::with_context(context.clone(), NoopReaper)
+        .with_config(HarvestConfig::new().poll_timeout(Duration::from_millis(1)));
+    let summaries = harvester.reap_next_batch().await?;
+    assert!(matches!(summaries.as_slice(), [ReapSummary::Success(_)]));
+    assert!(harvester.get_reap_failed_job_ids().await?.is_empty());
+
+    let timeline = inspector.get_job_timeline(&job_id).await?;
+    let kinds: Vec<JobEventKind> = timeline.iter().map(|entry| entry.kind).collect();
+    assert!(kinds.ends_with(&[
+        JobEventKind::ReapFailed,
+        JobEventKind::ReapFailed,
+        JobEventKind::Replayed,
+        JobEventKind::Harvested,
+    ]));
+    let reap_failure = &timeline[timeline.len() - 3];
+    assert_eq!(
+        reap_failure.detail.as_deref(),
+        Some("Webhook rejected the result")
+    );
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+/// Lets the job metadata expire while reaping it, then fails
+struct ExpiringReaper {
+    context: Context,
+}
+
+impl Reaper for ExpiringReaper {
+    async fn reap(&self, load: &Reapload) -> Result<ReapSummary> {
+        use redis::AsyncCommands;
+
+        let mut conn = self.context.get_connection().await?;
+        let metadata_key = self.context.keys().job_metadata_hash(&load.job_id);
+        let _: () = conn.del(metadata_key).await?;
+        Ok(ReapSummary::Failure("Job vanished".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_reap_failure_of_expired_job() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit(&producer)
+        .await?;
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+    consumer.run_next().await?;
+
+    let reaper = ExpiringReaper {
+        context: context.clone(),
+    };
+    let harvester = Harvester::with_context(context.clone(), reaper)
+        .with_config(HarvestConfig::new().poll_timeout(Duration::from_millis(1)));
+    let summaries = harvester.reap_next_batch().await?;
+    assert!(matches!(summaries.as_slice(), [ReapSummary::Failure(_)]));
+
+    // the failure doesn't bring back a partial job that would be leased forever
+    let keys = context.keys();
+    let mut conn = context.get_connection().await?;
+    let exists: bool = conn.exists(keys.job_metadata_hash(&job_id)).await?;
+    assert!(!exists);
+    let harvesting: Option<f64> = conn.zscore(keys.harvesting_set(), &job_id).await?;
+    assert_eq!(harvesting, None);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reap_cleans_expired() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let producer = Producer::with_context(context.clone());
+    let job_id = JobPlan::new()
+        .payload(json!({"action": "test_action"}))
+        .submit(&producer)
+        .await?;
+    let consumer = Consumer::with_context(context.clone(), NoopWorker);
+    consumer.run_next().await?;
+
+    // pretend the result retention ran out before anyone harvested it
+    let mut conn = context.get_connection().await?;
+    let _: () = conn
+        .zadd(context.keys().completed_set(), &job_id, 1)
+        .await?;
+
+    let harvester = Harvester::with_context(context.clone(), NoopReaper).with_config(
+        HarvestConfig::new()
+            .poll_timeout(Duration::from_millis(1))
+            .cleanup_interval(Duration::from_millis(10)),
+    );
+    harvester
+        .reap_until(tokio::time::sleep(Duration::from_millis(50)))
+        .await?;
+
+    let completed: usize = conn.zcard(context.keys().completed_set()).await?;
+    assert_eq!(completed, 0);
+    assert!(!inspector.job_exists(&job_id).await?);
+    let outcome: bool = conn
+        .exists(context.keys().job_outcome_list(&job_id))
+        .await?;
+    assert!(!outcome);
+
+    producer.clean_job(&job_id).await?;
+    Ok(())
+}
@@ -21,6 +21,89 @@ async fn test_job_not_found_for_metadata() {
     ));
 }
 
+#[tokio::test]
+async fn test_job_metadata_fields() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let fixture = JobFixture::new(context.clone(), JobStatus::Completed, 0).await?;
+    let metadata_key = context.keys().job_metadata_hash(&fixture.job_id);
+
+    let history = r#"[{"attempt":1,"reason":"timeout","error":"Too slow","failed_at":1000}]"#;
+    let mut conn = context.get_connection().await?;
+    let _: () = redis::pipe()
+        .hset(&metadata_key, "attempt_count", "1")
+        .hset(&metadata_key, "attempt_history", history)
+        .hset(&metadata_key, "started_at", "2000")
+        .hset(&metadata_key, "completed_at", "3000")
+        .query_async(&mut conn)
+        .await?;
+
+    let metadata = inspector.get_job_metadata(&fixture.job_id).await?;
+    assert_eq!(metadata.status, JobStatus::Completed);
+    assert_eq!(metadata.attempt_count, 1);
+    assert_eq!(
+        metadata.attempt_history,
+        vec![AttemptRecord {
+            attempt: 1,
+            reason: "timeout".to_string(),
+            error: "Too slow".to_string(),
+            failed_at: 1000,
+        }]
+    );
+    assert_eq!(metadata.started_at, Some(2000));
+    assert_eq!(metadata.completed_at, Some(3000));
+    assert_eq!(metadata.aborted_at, None);
+    assert_eq!(metadata.perished_at, None);
+
+    let _: () = conn.hset(&metadata_key, "started_at", "yesterday").await?;
+    let error = inspector
+        .get_job_metadata(&fixture.job_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(&error, JonoError::InvalidJob(msg) if msg.contains("started_at")));
+
+    let _: () = conn.hset(&metadata_key, "attempt_history", "[{}]").await?;
+    let error = inspector
+        .get_job_metadata(&fixture.job_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(&error, JonoError::InvalidJob(msg) if msg.contains("attempt_history")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_metadata_without_status() -> Result<()> {
+    use redis::AsyncCommands;
+
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let now = current_timestamp_ms();
+    let queue_fix = JobFixture::new(context.clone(), JobStatus::Queued, 0).await?;
+    let postpone_fix = JobFixture::new(context.clone(), JobStatus::Postponed, now + 60000).await?;
+
+    let mut conn = context.get_connection().await?;
+    for job_id in [&queue_fix.job_id, &postpone_fix.job_id] {
+        let metadata_key = context.keys().job_metadata_hash(job_id);
+        let _: () = conn.hdel(&metadata_key, "status").await?;
+    }
+
+    let metadata = inspector.get_job_metadata(&queue_fix.job_id).await?;
+    assert_eq!(metadata.status, JobStatus::Queued);
+    let metadata = inspector.get_job_metadata(&postpone_fix.job_id).await?;
+    assert_eq!(metadata.status, JobStatus::Postponed);
+
+    let job_metadatas = inspector
+        .get_status_to_job_metadata(JobFilter::default())
+        .await?;
+    assert_eq!(job_metadatas.queued.len(), 1);
+    assert_eq!(job_metadatas.postponed.len(), 1);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_job_maps_on_all() -> Result<()> {
     let context = create_test_context();
@@ -32,6 +115,7 @@ async fn test_job_maps_on_all() -> Result<()> {
     let start_fix = JobFixture::new(context.clone(), JobStatus::Started, now + 10000).await?;
     let abort_fix = JobFixture::new(context.clone(), JobStatus::Aborted, now + 30000).await?;
     let complete_fix = JobFixture::new(context.clone(), JobStatus::Completed, now).await?;
+    let perish_fix = JobFixture::new(context.clone(), JobStatus::Perished, now).await?;
 
     let job_ids = inspector
         .get_status_to_job_ids(JobFilter::default())
@@ -45,12 +129,14 @@ async fn test_job_maps_on_all() -> Result<()> {
     assert_eq!(job_ids.started, vec![start_fix.job_id.clone()]);
     assert_eq!(job_ids.aborted, vec![abort_fix.job_id.clone()]);
     assert_eq!(job_ids.completed, vec![complete_fix.job_id.clone()]);
+    assert_eq!(job_ids.perished, vec![perish_fix.job_id.clone()]);
 
     assert_eq!(job_metadatas.postponed.len(), 1);
     assert_eq!(job_metadatas.queued.len(), 1);
     assert_eq!(job_metadatas.started.len(), 1);
     assert_eq!(job_metadatas.aborted.len(), 1);
     assert_eq!(job_metadatas.completed.len(), 1);
+    assert_eq!(job_metadatas.perished.len(), 1);
 
     Ok(())
 }
@@ -74,12 +160,14 @@ async fn test_job_maps_on_queued() -> Result<()> {
     assert!(job_ids.started.is_empty());
     assert!(job_ids.aborted.is_empty());
     assert!(job_ids.completed.is_empty());
+    assert!(job_ids.perished.is_empty());
 
     assert_eq!(job_metadatas.postponed.len(), 0);
     assert_eq!(job_metadatas.queued.len(), 1);
     assert_eq!(job_metadatas.started.len(), 0);
     assert_eq!(job_metadatas.aborted.len(), 0);
     assert_eq!(job_metadatas.completed.len(), 0);
+    assert_eq!(job_metadatas.perished.len(), 0);
 
     Ok(())
 }
@@ -104,12 +192,14 @@ async fn test_job_maps_on_started() -> Result<()> {
     assert_eq!(job_ids.started, vec![start_fix.job_id.clone()]);
     assert!(job_ids.aborted.is_empty());
     assert!(job_ids.completed.is_empty());
+    assert!(job_ids.perished.is_empty());
 
     assert_eq!(job_metadatas.postponed.len(), 0);
     assert_eq!(job_metadatas.queued.len(), 0);
     assert_eq!(job_metadatas.started.len(), 1);
     assert_eq!(job_metadatas.aborted.len(), 0);
     assert_eq!(job_metadatas.completed.len(), 0);
+    assert_eq!(job_metadatas.perished.len(), 0);
 
     Ok(())
 }
@@ -134,12 +224,14 @@ async fn test_job_map_on_postponed() -> Result<()> {
     assert!(job_ids.started.is_empty());
     assert!(job_ids.aborted.is_empty());
     assert!(job_ids.completed.is_empty());
+    assert!(job_ids.perished.is_empty());
 
     assert_eq!(job_metadatas.postponed.len(), 1);
     assert_eq!(job_metadatas.queued.len(), 0);
     assert_eq!(job_metadatas.started.len(), 0);
     assert_eq!(job_metadatas.aborted.len(), 0);
     assert_eq!(job_metadatas.completed.len(), 0);
+    assert_eq!(job_metadatas.perished.len(), 0);
 
     Ok(())
 }
@@ -164,12 +256,14 @@ async fn test_job_maps_on_aborted() -> Result<()> {
     assert!(job_ids.started.is_empty());
     assert_eq!(job_ids.aborted, vec![cancel_fix.job_id.clone()]);
     assert!(job_ids.completed.is_empty());
+    assert!(job_ids.perished.is_empty());
 
     assert_eq!(job_metadatas.postponed.len(), 0);
     assert_eq!(job_metadatas.queued.len(), 0);
     assert_eq!(job_metadatas.started.len(), 0);
     assert_eq!(job_metadatas.aborted.len(), 1);
     assert_eq!(job_metadatas.completed.len(), 0);
+    assert_eq!(job_metadatas.perished.len(), 0);
 
     Ok(())
 }
@@ -194,12 +288,51 @@ async fn test_job_maps_on_completed() -> Result<()> {
     assert!(job_ids.started.is_empty());
     assert!(job_ids.aborted.is_empty());
     assert_eq!(job_ids.completed, vec![complete_fix.job_id.clone()]);
+    assert!(job_ids.perished.is_empty());
 
     assert_eq!(job_metadatas.postponed.len(), 0);
     assert_eq!(job_metadatas.queued.len(), 0);
     assert_eq!(job_metadatas.started.len(), 0);
     assert_eq!(job_metadatas.aborted.len(), 0);
     assert_eq!(job_metadatas.completed.len(), 1);
+    assert_eq!(job_metadatas.perished.len(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_job_maps_on_perished() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+
+    let now = current_timestamp_ms();
+    let perish_fix = JobFixture::new(context.clone(), JobStatus::Perished, now).await?;
+
+    let job_ids = inspector
+        .get_status_to_job_ids(JobFilter::default())
+        .await?;
+    let job_metadatas = inspector
+        .get_status_to_job_metadata(JobFilter::default())
+        .await?;
+
+    assert!(job_ids.postponed.is_empty());
+    assert!(job_ids.queued.is_empty());
+    assert!(job_ids.started.is_empty());
+    assert!(job_ids.aborted.is_empty());
+    assert!(job_ids.completed.is_empty());
+    assert_eq!(job_ids.perished, vec![perish_fix.job_id.clone()]);
+
+    assert_eq!(job_metadatas.postponed.len(), 0);
+    assert_eq!(job_metadatas.queued.len(), 0);
+    assert_eq!(job_metadatas.started.len(), 0);
+    assert_eq!(job_metadatas.aborted.len(), 0);
+    assert_eq!(job_metadatas.completed.len(), 0);
+    assert_eq!(job_metadatas.perished.len(), 1);
+
+    assert_eq!(
+        inspector.get_job_status(&perish_fix.job_id).await?,
+        JobStatus::Perished
+    );
 
     Ok(())
 }
@@ -1,4 +1,4 @@
-#![cfg(all(feature = "produce"))]
+#![cfg(feature = "produce")]
 
 mod common;
 
@@ -61,7 +61,7 @@ async fn test_job_origin() -> Result<()> {
     let origin = "my very special original hostname";
     let job_id = JobPlan::new()
         .payload(payload)
-        .origin(&origin)
+        .origin(origin)
         .submit(&producer)
         .await?;
 
@@ -137,3 +137,190 @@ async fn test_job_not_found_for_abort() {
         JonoError::JobNotFound(_)
     ));
 }
+
+#[tokio::test]
+async fn test_dedup_key() -> Result<()> {
+    use redis::AsyncCommands;
+    use std::time::Duration;
+
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let inspector = Inspector::with_context(context.clone());
+    let window = Duration::from_secs(60);
+
+    let job_id = JobPlan::new()
+        .payload(json!({"order": 1}))
+        .dedup_key("order-1", window)
+        .submit(&producer)
+        .await?;
+    let duplicate_id = JobPlan::new()
+        .payload(json!({"order": 1}))
+        .dedup_key("order-1", window)
+        .submit(&producer)
+        .await?;
+    assert_eq!(duplicate_id, job_id);
+
+    let other_id = JobPlan::new()
+        .payload(json!({"order": 2}))
+        .dedup_key("order-2", window)
+        .submit(&producer)
+        .await?;
+    assert_ne!(other_id, job_id);
+
+    let status_to_job_ids = inspector
+        .get_status_to_job_ids(JobFilter::default())
+        .await?;
+    assert_eq!(status_to_job_ids.queued.len(), 2);
+
+    // the key holds for the whole window even once the job is gone on its own,
+    // e.g. completed with discard retention
+    let mut conn = context.get_connection().await?;
+    let _: () = conn
+        .del(context.keys().job_metadata_hash(&other_id))
+        .await?;
+    let retried_id = JobPlan::new()
+        .payload(json!({"order": 2}))
+        .dedup_key("order-2", window)
+        .submit(&producer)
+        .await?;
+    assert_eq!(retried_id, other_id);
+
+    // a cleaned job doesn't block new submissions with its key
+    producer.clean_job(&job_id).await?;
+    let resubmitted_id = JobPlan::new()
+        .payload(json!({"order": 1}))
+        .dedup_key("order-1", window)
+        .submit(&producer)
+        .await?;
+    assert_ne!(resubmitted_id, job_id);
+    assert_eq!(
+        inspector.get_job_status(&resubmitted_id).await?,
+        JobStatus::Queued
+    );
+
+    producer.clean_job(&other_id).await?;
+    producer.clean_job(&resubmitted_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dedup_window() -> Result<()> {
+    use std::time::Duration;
+
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+
+    let job_id = JobPlan::new()
+        .payload(json!({"order": 1}))
+        .dedup_key("order-1", Duration::from_millis(50))
+        .submit(&producer)
+        .await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let later_id = JobPlan::new()
+        .payload(json!({"order": 1}))
+        .dedup_key("order-1", Duration::from_millis(50))
+        .submit(&producer)
+        .await?;
+    assert_ne!(later_id, job_id);
+
+    let invalid = JobPlan::new()
+        .payload(json!({"order": 1}))
+        .dedup_key("order-1", Duration::ZERO)
+        .submit(&producer)
+        .await;
+    assert!(matches!(invalid, Err(JonoError::InvalidJob(_))));
+
+    producer.clean_job(&job_id).await?;
+    producer.clean_job(&later_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_custom_job_id() -> Result<()> {
+    use redis::AsyncCommands;
+    use std::time::Duration;
+
+    let context = create_test_context();
+    let producer = Producer::with_context(context.clone());
+    let inspector = Inspector::with_context(context.clone());
+
+    let custom_id = format!("order:{}", generate_job_id());
+    let job_id = JobPlan::new()
+        .job_id(&custom_id)
+        .payload(json!({"order": 1}))
+        .submit(&producer)
+        .await?;
+    assert_eq!(job_id, custom_id);
+    assert_eq!(inspector.get_job_metadata(&job_id).await?.id, custom_id);
+    assert_eq!(inspector.get_job_status(&job_id).await?, JobStatus::Queued);
+
+    // the ID is taken
+    let collision = JobPlan::new()
+        .job_id(&custom_id)
+        .payload(json!({"order": 2}))
+        .submit(&producer)
+        .await;
+    assert!(matches!(collision, Err(JonoError::JobExists(id)) if id == custom_id));
+    let metadata = inspector.get_job_metadata(&job_id).await?;
+    assert_eq!(metadata.payload, json!({"order": 1}));
+
+    // but resubmitting within the dedup window is not a collision
+    let deduped_id = format!("order:{}", generate_job_id());
+    for _ in 0..2 {
+        let job_id = JobPlan::new()
+            .job_id(&deduped_id)
+            .payload(json!({"order": 3}))
+            .dedup_key(&deduped_id, Duration::from_secs(60))
+            .submit(&producer)
+            .await?;
+        assert_eq!(job_id, deduped_id);
+    }
+
+    // and the ID can be used again once the job is gone
+    producer.clean_job(&custom_id).await?;
+    let reused_id = JobPlan::new()
+        .job_id(&custom_id)
+        .payload(json!({"order": 4}))
+        .submit(&producer)
+        .await?;
+    assert_eq!(reused_id, custom_id);
+
+    // a completed job whose metadata has expired keeps its ID until it's cleaned up
+    let expired_id = format!("order:{}", generate_job_id());
+    let mut conn = context.get_connection().await?;
+    let _: () = conn
+        .zadd(context.keys().completed_set(), &expired_id, 0)
+        .await?;
+    let collision = JobPlan::new()
+        .job_id(&expired_id)
+        .payload(json!({"order": 5}))
+        .submit(&producer)
+        .await;
+    assert!(matches!(collision, Err(JonoError::JobExists(id)) if id == expired_id));
+
+    producer.clean_job(&custom_id).await?;
+    producer.clean_job(&deduped_id).await?;
+    producer.clean_job(&expired_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_custom_job_id() -> Result<()> {
+    let context = create_test_context();
+    let producer = Producer::with_context(context);
+
+    for invalid_id in ["", "has space", "curly{brace}", &"x".repeat(129)] {
+        let submitted = JobPlan::new()
+            .job_id(invalid_id)
+            .payload(json!({"order": 1}))
+            .submit(&producer)
+            .await;
+        assert!(
+            matches!(submitted, Err(JonoError::InvalidJob(_))),
+            "{:?} should be rejected",
+            invalid_id
+        );
+    }
+    Ok(())
+}
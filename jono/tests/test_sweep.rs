@@ -0,0 +1,98 @@
+#![cfg(feature = "consume")]
+
+mod common;
+
+use common::{JobFixture, create_test_context};
+use jono::prelude::*;
+use jono_core::{Result, current_timestamp_ms};
+use redis::AsyncCommands;
+
+#[tokio::test]
+async fn test_sweep_perishes() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let sweeper = Sweeper::with_context(context.clone());
+
+    let now = current_timestamp_ms();
+    let alive_fix = JobFixture::new(context.clone(), JobStatus::Started, now + 10000).await?;
+    let dead_fix = JobFixture::new(context.clone(), JobStatus::Started, now - 1000).await?;
+
+    assert_eq!(sweeper.sweep_expired_jobs().await?, 1);
+    assert_eq!(
+        inspector.get_job_status(&alive_fix.job_id).await?,
+        JobStatus::Started
+    );
+    assert_eq!(
+        inspector.get_job_status(&dead_fix.job_id).await?,
+        JobStatus::Perished
+    );
+    assert_eq!(
+        inspector
+            .get_job_metadata(&dead_fix.job_id)
+            .await?
+            .attempt_count,
+        1
+    );
+
+    // the same run is never recovered twice
+    assert_eq!(sweeper.sweep_expired_jobs().await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sweep_requeues() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let sweeper = Sweeper::with_context(context.clone());
+
+    let now = current_timestamp_ms();
+    let dead_fix = JobFixture::new(context.clone(), JobStatus::Started, now - 1000).await?;
+
+    let mut conn = context.get_connection().await?;
+    let metadata_key = context.keys().job_metadata_hash(&dead_fix.job_id);
+    let _: () = conn.hset(&metadata_key, "max_attempts", "2").await?;
+
+    assert_eq!(sweeper.sweep_expired_jobs().await?, 1);
+    assert_eq!(
+        inspector.get_job_status(&dead_fix.job_id).await?,
+        JobStatus::Queued
+    );
+
+    let history: String = conn.hget(&metadata_key, "attempt_history").await?;
+    let history: serde_json::Value = serde_json::from_str(&history)?;
+    assert_eq!(history[0]["reason"], "heartbeat_lost");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sweep_unreadable_retry_policy() -> Result<()> {
+    let context = create_test_context();
+    let inspector = Inspector::with_context(context.clone());
+    let sweeper = Sweeper::with_context(context.clone());
+
+    let now = current_timestamp_ms();
+    let dead_fix = JobFixture::new(context.clone(), JobStatus::Started, now - 1000).await?;
+
+    let mut conn = context.get_connection().await?;
+    let metadata_key = context.keys().job_metadata_hash(&dead_fix.job_id);
+    let _: () = redis::pipe()
+        .hset(&metadata_key, "max_attempts", "2")
+        .hset(
+            &metadata_key,
+            "retry_policy",
+            r#"{"kind":"from_the_future"}"#,
+        )
+        .query_async(&mut conn)
+        .await?;
+
+    // falls back to the default policy instead of leaving the job started forever
+    assert_eq!(sweeper.sweep_expired_jobs().await?, 1);
+    assert_eq!(
+        inspector.get_job_status(&dead_fix.job_id).await?,
+        JobStatus::Queued
+    );
+
+    Ok(())
+}
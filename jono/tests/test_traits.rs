@@ -10,7 +10,7 @@ async fn context_is_send_sync() -> Result<()> {
     Ok(())
 }
 
-#[cfg(all(feature = "consume"))]
+#[cfg(feature = "consume")]
 #[tokio::test]
 async fn workload_is_send_sync() -> Result<()> {
     use jono_consume::Workload;
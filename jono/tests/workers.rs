@@ -8,3 +8,12 @@ impl Worker for NoopWorker {
         Ok(WorkSummary::Success(Some(json!({"processed": true}))))
     }
 }
+
+#[allow(dead_code)]
+pub struct FailWorker;
+
+impl Worker for FailWorker {
+    async fn work(&self, _: &Workload) -> jono_core::Result<WorkSummary> {
+        Ok(WorkSummary::Failure("nope".to_string()))
+    }
+}
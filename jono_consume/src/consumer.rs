@@ -1,15 +1,46 @@
 use crate::consumer_config::ConsumerConfig;
-use crate::{WorkSummary, Worker, Workload};
-use jono_core::{Context, Inspector, JonoError, Result, current_timestamp_ms};
+use crate::scripts::{
+    CLAIM_SCRIPT, COMPLETE_SCRIPT, HEARTBEAT_SCRIPT, MARK_ABORTED_SCRIPT, RELEASE_SCRIPT,
+    RESCHEDULE_SCRIPT, discard_started_job, fail_started_job,
+};
+use crate::{Scheduler, Sweeper, WorkSummary, Worker, Workload};
+use futures_util::future::{Either, select};
+use futures_util::stream::FuturesUnordered;
+use futures_util::{FutureExt, StreamExt};
+use jono_core::{
+    Context, Inspector, JobOutcome, JonoError, Result, Retention, current_timestamp_ms,
+    generate_job_id, transition_args,
+};
 use redis::AsyncCommands;
+use redis::aio::MultiplexedConnection;
 use serde_json::json;
-use std::thread;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
 /// Interface for getting, processing and resolving jobs from Jono queues.
 pub struct Consumer<W: Worker> {
     context: Context,
     config: ConsumerConfig,
     worker: W,
+
+    /// Set once a shutdown is requested; no new jobs are claimed after that
+    stopping: AtomicBool,
+
+    /// Jobs claimed by this consumer that haven't been resolved yet, with the claim IDs
+    /// of their runs
+    in_progress: Mutex<HashMap<String, String>>,
+
+    /// When to next promote due jobs and recover jobs with expired heartbeats
+    next_maintenance: Mutex<Instant>,
+
+    /// Dedicated connections for waiting on the wake-up list, kept between the waits;
+    /// there are only as many as there have been waits at once
+    wake_up_conns: Mutex<Vec<MultiplexedConnection>>,
 }
 
 impl<W: Worker> Consumer<W> {
@@ -18,6 +49,10 @@ impl<W: Worker> Consumer<W> {
             context,
             config: ConsumerConfig::default(),
             worker,
+            stopping: AtomicBool::new(false),
+            in_progress: Mutex::new(HashMap::new()),
+            next_maintenance: Mutex::new(Instant::now()),
+            wake_up_conns: Mutex::new(Vec::new()),
         }
     }
 
@@ -26,17 +61,38 @@ impl<W: Worker> Consumer<W> {
         self
     }
 
+    /// Keep processing jobs, with up to `concurrency` jobs in flight at once
     pub async fn run(&self) -> Result<()> {
+        self.run_until(std::future::pending()).await
+    }
+
+    /// Keep processing jobs until the `shutdown` future resolves, then stop claiming new jobs
+    /// and wait for the jobs in flight; jobs still running at the shutdown timeout are put
+    /// back to the queue
+    pub async fn run_until(&self, shutdown: impl Future<Output = ()>) -> Result<()> {
+        self.stopping.store(false, Ordering::SeqCst);
+        let mut shutdown = pin!(shutdown);
         let mut consecutive_errors = 0;
+        let mut in_flight = FuturesUnordered::new();
+        let mut delay = Duration::ZERO;
 
         loop {
-            match self.run_next().await {
+            while in_flight.len() < self.config.get_concurrency().max(1) {
+                in_flight.push(self.run_next_after(delay));
+            }
+
+            let result = match select(in_flight.next(), shutdown.as_mut()).await {
+                Either::Left((Some(result), _)) => result,
+                Either::Left((None, _)) => continue,
+                Either::Right(((), _)) => break,
+            };
+
+            delay = match result {
                 Ok(Some(_)) => {
                     consecutive_errors = 0;
+                    Duration::ZERO
                 }
-                Ok(None) => {
-                    thread::sleep(self.config.get_poll_interval());
-                }
+                Ok(None) => self.config.get_poll_interval(),
                 Err(e) => {
                     consecutive_errors += 1;
                     eprintln!("Error processing job: {}", e);
@@ -44,56 +100,161 @@ impl<W: Worker> Consumer<W> {
                     if consecutive_errors >= self.config.get_max_consecutive_errors() {
                         return Err(JonoError::TooManyErrors(consecutive_errors));
                     }
-                    thread::sleep(self.config.get_poll_interval());
+                    self.config.get_poll_interval()
+                }
+            };
+        }
+
+        self.stopping.store(true, Ordering::SeqCst);
+
+        let drain = pin!(async {
+            while let Some(result) = in_flight.next().await {
+                if let Err(e) = result {
+                    eprintln!("Error processing job during shutdown: {}", e);
                 }
             }
+        });
+        let timeout = pin!(jono_core::sleep(self.config.get_shutdown_timeout()));
+        if let Either::Right(_) = select(drain, timeout).await {
+            eprintln!("Shutdown timeout reached, releasing unfinished jobs back to the queue");
+        }
+
+        self.release_in_progress_jobs().await
+    }
+
+    async fn run_next_after(&self, delay: Duration) -> Result<Option<WorkSummary>> {
+        if !delay.is_zero() {
+            jono_core::sleep(delay).await;
         }
+        self.run_next().await
     }
 
     pub async fn run_next(&self) -> Result<Option<WorkSummary>> {
         match self.start_next_job().await? {
-            Some(metadata) => {
-                let summary = self.process_job(metadata).await?;
-                Ok(Some(summary))
+            Some(workload) => {
+                let job_id = workload.job_id.clone();
+                let summary = self.process_job(workload).await;
+                self.in_progress().remove(&job_id);
+                Ok(Some(summary?))
             }
             None => Ok(None),
         }
     }
 
+    /// Claim the next job, blocking until a job is queued or the poll timeout passes;
+    /// the wait is cut short for the maintenance that can promote or recover jobs
     async fn start_next_job(&self) -> Result<Option<Workload>> {
-        let mut conn = self.get_connection().await?;
-        let timeout = self.config.get_poll_timeout().as_secs_f64();
-        let keys = self.context.keys();
+        let deadline = Instant::now() + self.config.get_poll_timeout();
+
+        loop {
+            if self.stopping.load(Ordering::SeqCst) {
+                return Ok(None);
+            }
 
-        let entries: Vec<(String, String, i64)> = conn.bzpopmin(keys.queued_set(), timeout).await?;
+            self.maintain_if_due().await;
 
-        if let Some((_set, job_id, _score)) = entries.first() {
-            let inspector = Inspector::with_context(self.context.clone());
-            let metadata = inspector.get_job_metadata(job_id).await?;
-            let workload = Workload::from_metadata(metadata);
-            self.mark_job_as_started(job_id).await?;
-            Ok(Some(workload))
-        } else {
-            Ok(None)
+            if let Some((job_id, claim_id)) = self.claim_next_job().await? {
+                // a job whose metadata can't be read is left for the sweeper to recover,
+                // so it's only tracked for release once there is a workload to run
+                let inspector = Inspector::with_context(self.context.clone());
+                let metadata = inspector.get_job_metadata(&job_id).await?;
+                let mut workload = Workload::from_metadata(metadata);
+                self.in_progress().insert(job_id, claim_id.clone());
+                workload.claim_id = claim_id;
+                return Ok(Some(workload));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let wait = remaining.min(self.until_next_maintenance());
+            if wait.is_zero() {
+                continue;
+            }
+
+            let mut conn = self.take_wake_up_connection().await?;
+            // zero would block forever
+            let _: Option<(String, String)> = conn
+                .blpop(
+                    self.context.keys().queued_wake_up_list(),
+                    wait.as_secs_f64().max(0.001),
+                )
+                .await?;
+            self.wake_up_conns().push(conn);
         }
     }
 
-    async fn mark_job_as_started(&self, job_id: &str) -> Result<()> {
+    /// Reuse an idle wake-up connection or open a new one; blocking would hold
+    /// a connection of the shared pool for the whole wait. A connection that fails
+    /// a wait is dropped instead of put back.
+    async fn take_wake_up_connection(&self) -> Result<MultiplexedConnection> {
+        let idle = self.wake_up_conns().pop();
+        match idle {
+            Some(conn) => Ok(conn),
+            None => self.context.get_dedicated_connection().await,
+        }
+    }
+
+    fn wake_up_conns(&self) -> MutexGuard<'_, Vec<MultiplexedConnection>> {
+        self.wake_up_conns
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn until_next_maintenance(&self) -> Duration {
+        let next_maintenance = *self
+            .next_maintenance
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        next_maintenance.saturating_duration_since(Instant::now())
+    }
+
+    /// Promote due postponed jobs and recover jobs with expired heartbeats once every
+    /// maintenance interval; errors are only logged so that they never block claiming jobs
+    async fn maintain_if_due(&self) {
+        {
+            let mut next_maintenance = self
+                .next_maintenance
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            if now < *next_maintenance {
+                return;
+            }
+            *next_maintenance = now + self.config.get_maintenance_interval();
+        }
+
+        let scheduler = Scheduler::with_context(self.context.clone());
+        if let Err(e) = scheduler.promote_due_jobs().await {
+            eprintln!("Error promoting due jobs: {}", e);
+        }
+        let sweeper = Sweeper::with_context(self.context.clone());
+        if let Err(e) = sweeper.sweep_expired_jobs().await {
+            eprintln!("Error recovering jobs with expired heartbeats: {}", e);
+        }
+    }
+
+    /// Atomically pop the next job from the queue and mark it as started;
+    /// returns the job ID and the claim ID of the new run
+    async fn claim_next_job(&self) -> Result<Option<(String, String)>> {
         let mut conn = self.get_connection().await?;
         let keys = self.context.keys();
         let now = current_timestamp_ms();
         let expiry = now + self.config.get_heartbeat_timeout().as_millis() as i64;
-        let metadata_key = keys.job_metadata_hash(job_id);
-
-        let _: () = redis::pipe()
-            .atomic()
-            .zadd(keys.started_set(), job_id, expiry)
-            .hset(&metadata_key, "status", "started")
-            .hset(&metadata_key, "started_at", now.to_string())
-            .query_async(&mut conn)
+
+        let claimed: Option<(String, String)> = CLAIM_SCRIPT
+            .key(keys.queued_set())
+            .key(keys.started_set())
+            .arg(now)
+            .arg(expiry)
+            .arg(keys.job_metadata_hash_prefix())
+            .arg(generate_job_id())
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
             .await?;
 
-        Ok(())
+        Ok(claimed)
     }
 
     async fn process_job(&self, workload: Workload) -> Result<WorkSummary> {
@@ -102,56 +263,322 @@ impl<W: Worker> Consumer<W> {
             return Ok(WorkSummary::Failure("Job no longer exists".to_string()));
         }
         if inspector.is_job_aborted(&workload.job_id).await? {
+            self.record_aborted(&workload).await?;
             return Ok(WorkSummary::Failure("Job was canceled".to_string()));
         }
 
-        let summary = self.worker.work(&workload).await?;
+        let summary = match self.work_with_heartbeat(&workload).await {
+            WorkOutcome::Finished(summary) => summary,
+            WorkOutcome::Aborted => {
+                eprintln!("Job {} aborted after its grace period", workload.job_id);
+                self.record_aborted(&workload).await?;
+                return Ok(WorkSummary::Failure("Job was aborted".to_string()));
+            }
+            WorkOutcome::TimedOut(timeout) => {
+                let error_message = format!("Job timed out after {} ms", timeout.as_millis());
+                eprintln!("Job {} failed: {}", workload.job_id, error_message);
+                fail_started_job(
+                    &self.context,
+                    &workload.job_id,
+                    Some(&workload.claim_id),
+                    i64::MAX,
+                    "timeout",
+                    &error_message,
+                )
+                .await?;
+                return Ok(WorkSummary::Failure(error_message));
+            }
+            WorkOutcome::Errored(error) => {
+                let error_message = format!("Worker failed: {}", error);
+                eprintln!("Job {} failed: {}", workload.job_id, error_message);
+                fail_started_job(
+                    &self.context,
+                    &workload.job_id,
+                    Some(&workload.claim_id),
+                    i64::MAX,
+                    "error",
+                    &error_message,
+                )
+                .await?;
+                return Ok(WorkSummary::Failure(error_message));
+            }
+            WorkOutcome::Panicked(panic_message) => {
+                let error_message = format!("Worker panicked: {}", panic_message);
+                eprintln!("Job {} failed: {}", workload.job_id, error_message);
+                fail_started_job(
+                    &self.context,
+                    &workload.job_id,
+                    Some(&workload.claim_id),
+                    i64::MAX,
+                    "panic",
+                    &error_message,
+                )
+                .await?;
+                return Ok(WorkSummary::Failure(error_message));
+            }
+        };
 
         match &summary {
             WorkSummary::Success(summary_data) => {
-                self.complete_job(&workload.job_id, summary_data.clone())
-                    .await?;
+                self.complete_job(&workload, summary_data.clone()).await?;
             }
             WorkSummary::Failure(error_message) => {
                 eprintln!("Job {} failed: {}", workload.job_id, error_message);
-                todo!();
+                self.fail_job(&workload, error_message).await?;
+            }
+            WorkSummary::Retry(delay) => {
+                let run_at = current_timestamp_ms() + delay.as_millis() as i64;
+                self.reschedule_job(&workload, run_at).await?;
+            }
+            WorkSummary::Snooze(run_at) => {
+                self.reschedule_job(&workload, *run_at).await?;
+            }
+            WorkSummary::Discard(reason) => {
+                eprintln!("Job {} discarded: {}", workload.job_id, reason);
+                discard_started_job(
+                    &self.context,
+                    &workload.job_id,
+                    &workload.claim_id,
+                    "discard",
+                    reason,
+                )
+                .await?;
             }
         }
 
         Ok(summary)
     }
 
+    /// Run the worker while keeping the job heartbeat alive; the heartbeat stops with the work,
+    /// and the work is dropped if the job is aborted or runs over its timeout.
+    /// An error or a panic in the worker only fails the attempt.
+    async fn work_with_heartbeat(&self, workload: &Workload) -> WorkOutcome {
+        let timeout = workload.timeout.or(self.config.get_job_timeout());
+        let work = pin!(AssertUnwindSafe(self.worker.work(workload)).catch_unwind());
+        let heartbeat = pin!(self.keep_heartbeat(workload));
+        let time_limit = pin!(async {
+            match timeout {
+                Some(timeout) => jono_core::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        });
+
+        match select(work, select(heartbeat, time_limit)).await {
+            Either::Left((Ok(Ok(summary)), _)) => WorkOutcome::Finished(summary),
+            Either::Left((Ok(Err(error)), _)) => WorkOutcome::Errored(error),
+            Either::Left((Err(panic), _)) => WorkOutcome::Panicked(panic_message(&*panic)),
+            Either::Right((Either::Left(_), _)) => WorkOutcome::Aborted,
+            Either::Right((Either::Right(_), _)) => {
+                WorkOutcome::TimedOut(timeout.unwrap_or_default())
+            }
+        }
+    }
+
+    /// Push the job heartbeat expiry forward every heartbeat interval and watch for aborts;
+    /// trips the abort signal of the workload and returns once the abort grace period ends
+    async fn keep_heartbeat(&self, workload: &Workload) {
+        let job_id = &workload.job_id;
+        let mut grace_end: Option<i64> = None;
+
+        loop {
+            let mut wait = self.config.get_heartbeat_interval();
+            if let Some(grace_end) = grace_end {
+                let remaining = grace_end - current_timestamp_ms();
+                if remaining <= 0 {
+                    return;
+                }
+                wait = wait.min(Duration::from_millis(remaining as u64));
+            }
+            jono_core::sleep(wait).await;
+
+            match self.beat_heartbeat(workload).await {
+                Ok(Some(abort_grace_end)) => {
+                    if grace_end.is_none() {
+                        workload.abort_signal().trip();
+                    }
+                    grace_end = Some(abort_grace_end);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Error updating heartbeat of job {}: {}", job_id, e),
+            }
+        }
+    }
+
+    /// Update the heartbeat of the run of a started job; returns the abort grace period end
+    /// if the job was aborted
+    async fn beat_heartbeat(&self, workload: &Workload) -> Result<Option<i64>> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+        let job_id = &workload.job_id;
+        let expiry =
+            current_timestamp_ms() + self.config.get_heartbeat_timeout().as_millis() as i64;
+
+        let grace_end: Option<f64> = HEARTBEAT_SCRIPT
+            .key(keys.started_set())
+            .key(keys.aborted_set())
+            .key(keys.job_metadata_hash(job_id))
+            .arg(job_id)
+            .arg(expiry)
+            .arg(&workload.claim_id)
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(grace_end.map(|score| score as i64))
+    }
+
+    /// Take an aborted job out of the started set and mark it as aborted
+    async fn record_aborted(&self, workload: &Workload) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+        let job_id = &workload.job_id;
+
+        let _: bool = MARK_ABORTED_SCRIPT
+            .key(keys.started_set())
+            .key(keys.job_metadata_hash(job_id))
+            .arg(job_id)
+            .arg(current_timestamp_ms())
+            .arg(&workload.claim_id)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
     async fn complete_job(
         &self,
-        job_id: &str,
+        workload: &Workload,
         work_summary: Option<serde_json::Value>,
     ) -> Result<()> {
         let mut conn = self.get_connection().await?;
         let keys = self.context.keys();
+        let job_id = &workload.job_id;
 
+        let outcome_json = serde_json::to_string(&JobOutcome::Completed(work_summary.clone()))?;
         let summ_data = work_summary.unwrap_or(json!(null));
         let summ_json = serde_json::to_string(&summ_data)?;
-        let metadata_key = keys.job_metadata_hash(job_id);
 
-        let now = current_timestamp_ms();
-        let ttl_ms = 24 * 60 * 60 * 1000; // 24 hours to collect the work summaries
-        let expiry_time_score = now + ttl_ms;
-
-        let _: () = redis::pipe()
-            .atomic()
-            .zrem(keys.started_set(), job_id)
-            .zadd(keys.completed_set(), job_id, expiry_time_score)
-            .hset(&metadata_key, "status", "completed")
-            .hset(&metadata_key, "completed_at", now.to_string())
-            .hset(&metadata_key, "work_summary", summ_json)
-            .expire(&metadata_key, ttl_ms / 1000)
-            .query_async(&mut conn)
+        let (retention, ttl_ms) = match workload.retention.unwrap_or(self.config.get_retention()) {
+            Retention::Discard => ("discard", 0),
+            Retention::Expire { ttl_ms } => ("expire", ttl_ms),
+            Retention::Forever => ("forever", 0),
+        };
+
+        let completed: Option<String> = COMPLETE_SCRIPT
+            .key(keys.started_set())
+            .key(keys.aborted_set())
+            .key(keys.completed_set())
+            .key(keys.job_metadata_hash(job_id))
+            .arg(job_id)
+            .arg(current_timestamp_ms())
+            .arg(retention)
+            .arg(ttl_ms)
+            .arg(summ_json)
+            .arg(outcome_json)
+            .arg(&workload.claim_id)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
             .await?;
 
+        if completed.is_none() {
+            eprintln!("Job {} was no longer started, not completing it", job_id);
+        }
+        Ok(())
+    }
+
+    /// Record a failed attempt and retry the job if it still has attempts left
+    async fn fail_job(&self, workload: &Workload, error_message: &str) -> Result<()> {
+        fail_started_job(
+            &self.context,
+            &workload.job_id,
+            Some(&workload.claim_id),
+            i64::MAX,
+            "failure",
+            error_message,
+        )
+        .await?;
         Ok(())
     }
 
+    /// Put the job back to be run again at the given timestamp without using an attempt
+    async fn reschedule_job(&self, workload: &Workload, run_at: i64) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+        let job_id = &workload.job_id;
+
+        let _: Option<String> = RESCHEDULE_SCRIPT
+            .key(keys.started_set())
+            .key(keys.queued_set())
+            .key(keys.postponed_set())
+            .key(keys.aborted_set())
+            .key(keys.job_metadata_hash(job_id))
+            .arg(job_id)
+            .arg(current_timestamp_ms())
+            .arg(run_at)
+            .arg(&workload.claim_id)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Put jobs this consumer didn't get to finish back to the queue without using an attempt
+    async fn release_in_progress_jobs(&self) -> Result<()> {
+        let runs: Vec<(String, String)> = self.in_progress().drain().collect();
+        if runs.is_empty() {
+            return Ok(());
+        }
+
+        let keys = self.context.keys();
+
+        for (job_id, claim_id) in runs {
+            let mut conn = self.get_connection().await?;
+            let outcome: Option<String> = RELEASE_SCRIPT
+                .key(keys.started_set())
+                .key(keys.queued_set())
+                .key(keys.aborted_set())
+                .key(keys.job_metadata_hash(&job_id))
+                .arg(&job_id)
+                .arg(&claim_id)
+                .arg(transition_args(&self.context))
+                .invoke_async(&mut conn)
+                .await?;
+            drop(conn);
+            if outcome.as_deref() == Some("released") {
+                eprintln!("Job {} released back to the queue", job_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn in_progress(&self) -> MutexGuard<'_, HashMap<String, String>> {
+        self.in_progress
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     async fn get_connection(&self) -> Result<impl redis::aio::ConnectionLike> {
         self.context.get_connection().await
     }
 }
+
+/// How running the worker on a job ended
+enum WorkOutcome {
+    Finished(WorkSummary),
+    Aborted,
+    TimedOut(Duration),
+    Errored(JonoError),
+    Panicked(String),
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
@@ -1,12 +1,13 @@
+use jono_core::Retention;
 use std::time::Duration;
 
 /// Configuration options for a Consumer
 #[derive(Debug, Clone)]
 pub struct ConsumerConfig {
-    /// How long to wait between polling for new jobs
+    /// How long to wait before polling again after an empty poll or an error
     poll_interval: Duration,
 
-    /// How long to wait for a job to become available each poll or "blocking"
+    /// How long to wait for a job to become available on each `run_next`
     poll_timeout: Duration,
 
     /// How often to update the heartbeat
@@ -15,8 +16,23 @@ pub struct ConsumerConfig {
     /// How long before a job is considered abandoned
     heartbeat_timeout: Duration,
 
+    /// How often to promote due postponed jobs and recover jobs with expired heartbeats
+    maintenance_interval: Duration,
+
     /// Maximum number of consecutive errors before stopping
     max_consecutive_errors: usize,
+
+    /// Maximum number of jobs processed at once by `Consumer::run`
+    concurrency: usize,
+
+    /// How long to wait for jobs in flight to finish after a shutdown request
+    shutdown_timeout: Duration,
+
+    /// How long a job may run before it's canceled, unless the job has its own timeout
+    job_timeout: Option<Duration>,
+
+    /// How long to keep results of completed jobs, unless the job has its own retention
+    retention: Retention,
 }
 
 impl Default for ConsumerConfig {
@@ -26,7 +42,12 @@ impl Default for ConsumerConfig {
             poll_timeout: Duration::from_secs(5),
             heartbeat_interval: Duration::from_secs(5),
             heartbeat_timeout: Duration::from_secs(10),
+            maintenance_interval: Duration::from_secs(1),
             max_consecutive_errors: 3,
+            concurrency: 1,
+            shutdown_timeout: Duration::from_secs(30),
+            job_timeout: None,
+            retention: Retention::default(),
         }
     }
 }
@@ -68,6 +89,14 @@ impl ConsumerConfig {
         self.heartbeat_timeout
     }
 
+    pub fn maintenance_interval(mut self, maintenance_interval: Duration) -> ConsumerConfig {
+        self.maintenance_interval = maintenance_interval;
+        self
+    }
+    pub fn get_maintenance_interval(&self) -> Duration {
+        self.maintenance_interval
+    }
+
     pub fn max_consecutive_errors(mut self, max_consecutive_errors: usize) -> ConsumerConfig {
         self.max_consecutive_errors = max_consecutive_errors;
         self
@@ -75,4 +104,36 @@ impl ConsumerConfig {
     pub fn get_max_consecutive_errors(&self) -> usize {
         self.max_consecutive_errors
     }
+
+    pub fn concurrency(mut self, concurrency: usize) -> ConsumerConfig {
+        self.concurrency = concurrency;
+        self
+    }
+    pub fn get_concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> ConsumerConfig {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+    pub fn get_shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    pub fn job_timeout(mut self, job_timeout: Duration) -> ConsumerConfig {
+        self.job_timeout = Some(job_timeout);
+        self
+    }
+    pub fn get_job_timeout(&self) -> Option<Duration> {
+        self.job_timeout
+    }
+
+    pub fn retention(mut self, retention: Retention) -> ConsumerConfig {
+        self.retention = retention;
+        self
+    }
+    pub fn get_retention(&self) -> Retention {
+        self.retention
+    }
 }
@@ -4,12 +4,19 @@
 
 mod consumer;
 mod consumer_config;
+mod scheduler;
+mod scripts;
+mod sweeper;
 mod worker;
 
 pub use consumer::Consumer;
 pub use consumer_config::ConsumerConfig;
-pub use worker::{WorkSummary, Worker, Workload};
+pub use scheduler::Scheduler;
+pub use sweeper::Sweeper;
+pub use worker::{AbortSignal, WorkSummary, Worker, Workload};
 
 pub mod prelude {
-    pub use crate::{Consumer, ConsumerConfig, WorkSummary, Worker, Workload};
+    pub use crate::{
+        AbortSignal, Consumer, ConsumerConfig, Scheduler, Sweeper, WorkSummary, Worker, Workload,
+    };
 }
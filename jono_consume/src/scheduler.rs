@@ -0,0 +1,31 @@
+use crate::scripts::PROMOTE_SCRIPT;
+use jono_core::{Context, Result, current_timestamp_ms, transition_args};
+
+/// Interface for promoting postponed jobs to the queue once they are due
+pub struct Scheduler {
+    context: Context,
+}
+
+impl Scheduler {
+    pub fn with_context(context: Context) -> Self {
+        Self { context }
+    }
+
+    /// Promote all postponed jobs that are due; safe to run from many processes at once
+    pub async fn promote_due_jobs(&self) -> Result<usize> {
+        let mut conn = self.context.get_connection().await?;
+        let keys = self.context.keys();
+        let now = current_timestamp_ms();
+
+        let promoted: Vec<String> = PROMOTE_SCRIPT
+            .key(keys.postponed_set())
+            .key(keys.queued_set())
+            .arg(now)
+            .arg(keys.job_metadata_hash_prefix())
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(promoted.len())
+    }
+}
@@ -0,0 +1,453 @@
+//! Server-side Lua scripts for the job state transitions that must be atomic.
+
+use jono_core::{
+    Context, JobOutcome, Result, RetryPolicy, current_timestamp_ms, transition_args,
+    transition_script,
+};
+use redis::AsyncCommands;
+use std::sync::LazyLock;
+
+/// Prepended to the scripts that resolve a run of a started job.
+/// `is_claimed_by(metadata_key, claim_id)` is false once another run has claimed the job;
+/// jobs claimed before claim IDs existed belong to any run.
+const RUN_LUA: &str = r#"
+local function is_claimed_by(metadata_key, claim_id)
+    local current_claim_id = redis.call('HGET', metadata_key, 'claim_id')
+    return not current_claim_id or current_claim_id == claim_id
+end
+"#;
+
+/// Create a transition script that resolves a run of a started job
+fn run_script(code: &str) -> redis::Script {
+    transition_script(&format!("{}{}", RUN_LUA, code))
+}
+
+/// Moves every postponed job that is due to the queued set at its initial priority.
+///
+/// KEYS[1] = postponed set, KEYS[2] = queued set
+/// ARGV[1] = current timestamp in milliseconds, ARGV[2] = job metadata hash key prefix,
+/// ARGV[3..] = transition arguments
+///
+/// Returns the IDs of the promoted jobs.
+pub(crate) static PROMOTE_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', '(' .. ARGV[1])
+        local promoted = {}
+        for _, job_id in ipairs(due) do
+            redis.call('ZREM', KEYS[1], job_id)
+            local metadata_key = ARGV[2] .. job_id
+            local priority = redis.call('HGET', metadata_key, 'initial_priority')
+            if priority then
+                redis.call('ZADD', KEYS[2], priority, job_id)
+                redis.call('HSET', metadata_key, 'status', 'queued')
+                record_event(job_id, 'promoted')
+                wake_up_consumers()
+                promoted[#promoted + 1] = job_id
+            end
+        end
+        return promoted
+        "#,
+    )
+});
+
+/// Pops the next job from the queue and marks it as started in one step,
+/// so that a claimed job is always either queued or started.
+/// Queued entries without metadata (cleaned jobs) are dropped on the way.
+/// The claim ID stamped in the metadata tells this run apart from later runs of the same job.
+///
+/// KEYS[1] = queued set, KEYS[2] = started set
+/// ARGV[1] = current timestamp in milliseconds, ARGV[2] = heartbeat expiry timestamp,
+/// ARGV[3] = job metadata hash key prefix, ARGV[4] = claim ID, ARGV[5..] = transition arguments
+///
+/// Returns the claimed job ID and the claim ID, or nil if the queue is empty.
+pub(crate) static CLAIM_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        while true do
+            local popped = redis.call('ZPOPMIN', KEYS[1])
+            if #popped == 0 then
+                return false
+            end
+            local job_id = popped[1]
+            local metadata_key = ARGV[3] .. job_id
+            if redis.call('EXISTS', metadata_key) == 1 then
+                redis.call('ZADD', KEYS[2], ARGV[2], job_id)
+                redis.call('HSET', metadata_key,
+                    'status', 'started',
+                    'started_at', ARGV[1],
+                    'claim_id', ARGV[4])
+                record_event(job_id, 'started')
+                return { job_id, ARGV[4] }
+            end
+        end
+        "#,
+    )
+});
+
+/// Pushes the heartbeat expiry of a started job forward, unless the job has been resolved,
+/// recovered or claimed by another run since.
+///
+/// KEYS[1] = started set, KEYS[2] = aborted set, KEYS[3] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = heartbeat expiry timestamp, ARGV[3] = claim ID of the run
+///
+/// Returns the abort grace period end if the job was aborted, nil otherwise.
+pub(crate) static HEARTBEAT_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    redis::Script::new(&format!(
+        "{}{}",
+        RUN_LUA,
+        r#"
+        if is_claimed_by(KEYS[3], ARGV[3]) then
+            redis.call('ZADD', KEYS[1], 'XX', ARGV[2], ARGV[1])
+        end
+        return redis.call('ZSCORE', KEYS[2], ARGV[1])
+        "#,
+    ))
+});
+
+/// Completes a started job and keeps its result by retention, or deletes the job right away.
+/// Jobs no longer started by this run, e.g. recovered by a sweeper and claimed again,
+/// are left untouched.
+///
+/// KEYS[1] = started set, KEYS[2] = aborted set, KEYS[3] = completed set,
+/// KEYS[4] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds,
+/// ARGV[3] = retention: "discard", "expire" or "forever", ARGV[4] = retention TTL in milliseconds,
+/// ARGV[5] = work summary JSON, ARGV[6] = outcome JSON, ARGV[7] = claim ID of the run,
+/// ARGV[8..] = transition arguments
+///
+/// Returns "completed", "discarded", "missing" or nil if the job wasn't started.
+pub(crate) static COMPLETE_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    run_script(
+        r#"
+        local job_id = ARGV[1]
+        if not is_claimed_by(KEYS[4], ARGV[7]) then
+            return false
+        end
+        if redis.call('ZREM', KEYS[1], job_id) == 0 then
+            return false
+        end
+        redis.call('ZREM', KEYS[2], job_id)
+        if redis.call('EXISTS', KEYS[4]) == 0 then
+            return 'missing'
+        end
+        if ARGV[3] == 'discard' then
+            redis.call('DEL', KEYS[4])
+            record_event(job_id, 'completed')
+            announce_outcome(job_id, ARGV[6])
+            return 'discarded'
+        end
+
+        redis.call('HSET', KEYS[4],
+            'status', 'completed',
+            'completed_at', ARGV[2],
+            'work_summary', ARGV[5])
+        if ARGV[3] == 'expire' then
+            redis.call('ZADD', KEYS[3], tonumber(ARGV[2]) + tonumber(ARGV[4]), job_id)
+            redis.call('PEXPIRE', KEYS[4], ARGV[4])
+        else
+            redis.call('ZADD', KEYS[3], '+inf', job_id)
+            redis.call('PERSIST', KEYS[4])
+        end
+        record_event(job_id, 'completed')
+        announce_outcome(job_id, ARGV[6])
+        return 'completed'
+        "#,
+    )
+});
+
+/// Marks a started job as aborted once its consumer has stopped working on it.
+/// Jobs no longer started by this run, e.g. recovered by a sweeper and claimed again,
+/// are left untouched.
+///
+/// KEYS[1] = started set, KEYS[2] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds, ARGV[3] = claim ID of the run,
+/// ARGV[4..] = transition arguments
+///
+/// Returns 1 if the job was marked as aborted, 0 otherwise.
+pub(crate) static MARK_ABORTED_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    run_script(
+        r#"
+        if not is_claimed_by(KEYS[2], ARGV[3]) then
+            return 0
+        end
+        if redis.call('ZREM', KEYS[1], ARGV[1]) == 0 then
+            return 0
+        end
+        if redis.call('EXISTS', KEYS[2]) == 0 then
+            return 0
+        end
+        redis.call('HSET', KEYS[2], 'status', 'aborted', 'aborted_at', ARGV[2])
+        record_event(ARGV[1], 'aborted')
+        announce_outcome(ARGV[1], aborted_outcome)
+        return 1
+        "#,
+    )
+});
+
+/// Puts a started job back to the queue at its initial priority without using an attempt,
+/// unless the job was aborted meanwhile.
+///
+/// KEYS[1] = started set, KEYS[2] = queued set, KEYS[3] = aborted set,
+/// KEYS[4] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = claim ID of the run, ARGV[3..] = transition arguments
+///
+/// Returns "released", "aborted", "missing" or nil if the job wasn't started by the run.
+pub(crate) static RELEASE_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    run_script(
+        r#"
+        local job_id = ARGV[1]
+        if not is_claimed_by(KEYS[4], ARGV[2]) then
+            return false
+        end
+        if redis.call('ZREM', KEYS[1], job_id) == 0 then
+            return false
+        end
+        if redis.call('EXISTS', KEYS[4]) == 0 then
+            return 'missing'
+        end
+        if redis.call('ZSCORE', KEYS[3], job_id) then
+            redis.call('HSET', KEYS[4], 'status', 'aborted')
+            record_event(job_id, 'aborted')
+            announce_outcome(job_id, aborted_outcome)
+            return 'aborted'
+        end
+        local priority = redis.call('HGET', KEYS[4], 'initial_priority')
+        if not priority then
+            return false
+        end
+        redis.call('ZADD', KEYS[2], priority, job_id)
+        redis.call('HSET', KEYS[4], 'status', 'queued')
+        record_event(job_id, 'released')
+        wake_up_consumers()
+        return 'released'
+        "#,
+    )
+});
+
+/// Moves a started job back to the queue, or to the postponed set if it should run later,
+/// without using an attempt; aborted jobs are only marked as aborted.
+///
+/// KEYS[1] = started set, KEYS[2] = queued set, KEYS[3] = postponed set, KEYS[4] = aborted set,
+/// KEYS[5] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds,
+/// ARGV[3] = when to run the job next as a timestamp in milliseconds,
+/// ARGV[4] = claim ID of the run, ARGV[5..] = transition arguments
+///
+/// Returns "requeued", "postponed", "aborted", "missing" or nil if the job wasn't started
+/// by the run.
+pub(crate) static RESCHEDULE_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    run_script(
+        r#"
+        local job_id = ARGV[1]
+        if not is_claimed_by(KEYS[5], ARGV[4]) then
+            return false
+        end
+        if redis.call('ZREM', KEYS[1], job_id) == 0 then
+            return false
+        end
+        if redis.call('EXISTS', KEYS[5]) == 0 then
+            return 'missing'
+        end
+        if redis.call('ZSCORE', KEYS[4], job_id) then
+            redis.call('HSET', KEYS[5], 'status', 'aborted')
+            record_event(job_id, 'aborted')
+            announce_outcome(job_id, aborted_outcome)
+            return 'aborted'
+        end
+
+        if tonumber(ARGV[3]) > tonumber(ARGV[2]) then
+            redis.call('ZADD', KEYS[3], ARGV[3], job_id)
+            redis.call('HSET', KEYS[5], 'status', 'postponed')
+            record_event(job_id, 'retried')
+            return 'postponed'
+        end
+        local priority = redis.call('HGET', KEYS[5], 'initial_priority') or '0'
+        redis.call('ZADD', KEYS[2], priority, job_id)
+        redis.call('HSET', KEYS[5], 'status', 'queued')
+        record_event(job_id, 'retried')
+        wake_up_consumers()
+        return 'requeued'
+        "#,
+    )
+});
+
+/// Records a failed attempt of a started job, then retries or dead-letters it by max attempts.
+/// Retries with a delay go through the postponed set, others straight back to the queue.
+/// Jobs that are not in the started set with a score below the deadline are left untouched,
+/// so the same run is never counted twice; with a claim ID, so are later runs of the job.
+///
+/// KEYS[1] = started set, KEYS[2] = queued set, KEYS[3] = postponed set, KEYS[4] = perished set,
+/// KEYS[5] = aborted set, KEYS[6] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds,
+/// ARGV[3] = only fail if the heartbeat expiry is below this, ARGV[4] = reason, ARGV[5] = error,
+/// ARGV[6] = retry delay in milliseconds, ARGV[7] = "1" to dead-letter without retrying,
+/// ARGV[8] = outcome JSON if the job perishes, ARGV[9] = claim ID of the run or empty for any run,
+/// ARGV[10..] = transition arguments
+///
+/// Returns "requeued", "postponed", "perished", "aborted", "missing"
+/// or nil if the job wasn't started by the run.
+pub(crate) static FAIL_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    run_script(
+        r#"
+        local job_id = ARGV[1]
+        local now = tonumber(ARGV[2])
+        if ARGV[9] ~= '' and not is_claimed_by(KEYS[6], ARGV[9]) then
+            return false
+        end
+        local expiry = redis.call('ZSCORE', KEYS[1], job_id)
+        if not expiry or tonumber(expiry) >= tonumber(ARGV[3]) then
+            return false
+        end
+        redis.call('ZREM', KEYS[1], job_id)
+
+        if redis.call('EXISTS', KEYS[6]) == 0 then
+            return 'missing'
+        end
+        if redis.call('ZSCORE', KEYS[5], job_id) then
+            redis.call('HSET', KEYS[6], 'status', 'aborted')
+            record_event(job_id, 'aborted')
+            announce_outcome(job_id, aborted_outcome)
+            return 'aborted'
+        end
+
+        local failure_kind = 'failed'
+        if ARGV[4] == 'heartbeat_lost' then
+            failure_kind = 'heartbeat_lost'
+        end
+        local attempt_count = tonumber(redis.call('HGET', KEYS[6], 'attempt_count') or '0') + 1
+        local max_attempts = tonumber(redis.call('HGET', KEYS[6], 'max_attempts') or '1')
+        local history = cjson.decode(redis.call('HGET', KEYS[6], 'attempt_history') or '[]')
+        history[#history + 1] = {
+            attempt = attempt_count,
+            reason = ARGV[4],
+            error = ARGV[5],
+            failed_at = now,
+        }
+        redis.call('HSET', KEYS[6],
+            'attempt_count', attempt_count,
+            'attempt_history', cjson.encode(history))
+        record_event(job_id, failure_kind, ARGV[5])
+
+        if attempt_count < max_attempts and ARGV[7] ~= '1' then
+            local retry_delay = tonumber(ARGV[6])
+            if retry_delay > 0 then
+                redis.call('ZADD', KEYS[3], now + retry_delay, job_id)
+                redis.call('HSET', KEYS[6], 'status', 'postponed')
+                record_event(job_id, 'retried')
+                return 'postponed'
+            end
+            local priority = redis.call('HGET', KEYS[6], 'initial_priority') or '0'
+            redis.call('ZADD', KEYS[2], priority, job_id)
+            redis.call('HSET', KEYS[6], 'status', 'queued')
+            record_event(job_id, 'retried')
+            wake_up_consumers()
+            return 'requeued'
+        end
+
+        redis.call('ZADD', KEYS[4], now, job_id)
+        redis.call('HSET', KEYS[6], 'status', 'perished', 'perished_at', now)
+        record_event(job_id, 'perished', ARGV[4])
+        announce_outcome(job_id, ARGV[8])
+        return 'perished'
+        "#,
+    )
+});
+
+/// Run the fail script for a job with a heartbeat expiry below the deadline,
+/// delaying the retry by the retry policy of the job; with a claim ID, only that run is failed
+pub(crate) async fn fail_started_job(
+    context: &Context,
+    job_id: &str,
+    claim_id: Option<&str>,
+    deadline: i64,
+    reason: &str,
+    error_message: &str,
+) -> Result<Option<String>> {
+    run_fail_script(
+        context,
+        job_id,
+        claim_id,
+        deadline,
+        reason,
+        error_message,
+        false,
+    )
+    .await
+}
+
+/// Run the fail script for a run of a started job so that it's dead-lettered regardless of
+/// attempts left
+pub(crate) async fn discard_started_job(
+    context: &Context,
+    job_id: &str,
+    claim_id: &str,
+    reason: &str,
+    error_message: &str,
+) -> Result<Option<String>> {
+    run_fail_script(
+        context,
+        job_id,
+        Some(claim_id),
+        i64::MAX,
+        reason,
+        error_message,
+        true,
+    )
+    .await
+}
+
+async fn run_fail_script(
+    context: &Context,
+    job_id: &str,
+    claim_id: Option<&str>,
+    deadline: i64,
+    reason: &str,
+    error_message: &str,
+    discard: bool,
+) -> Result<Option<String>> {
+    let mut conn = context.get_connection().await?;
+    let keys = context.keys();
+
+    let (policy_json, attempt_count): (Option<String>, Option<u32>) = conn
+        .hget(
+            keys.job_metadata_hash(job_id),
+            &["retry_policy", "attempt_count"],
+        )
+        .await?;
+    // a policy this version can't read must not keep the job from being failed
+    let retry_policy: RetryPolicy = match policy_json {
+        Some(policy_json) => serde_json::from_str(&policy_json).unwrap_or_else(|e| {
+            eprintln!(
+                "Invalid retry policy of job {}, using the default: {}",
+                job_id, e
+            );
+            RetryPolicy::default()
+        }),
+        None => RetryPolicy::default(),
+    };
+    let retry_delay = retry_policy.delay_after_attempt(attempt_count.unwrap_or(0) + 1);
+    let perished_outcome = JobOutcome::Perished(error_message.to_string());
+
+    let outcome: Option<String> = FAIL_SCRIPT
+        .key(keys.started_set())
+        .key(keys.queued_set())
+        .key(keys.postponed_set())
+        .key(keys.perished_set())
+        .key(keys.aborted_set())
+        .key(keys.job_metadata_hash(job_id))
+        .arg(job_id)
+        .arg(current_timestamp_ms())
+        .arg(deadline)
+        .arg(reason)
+        .arg(error_message)
+        .arg(retry_delay.as_millis() as u64)
+        .arg(if discard { "1" } else { "0" })
+        .arg(serde_json::to_string(&perished_outcome)?)
+        .arg(claim_id.unwrap_or(""))
+        .arg(transition_args(context))
+        .invoke_async(&mut conn)
+        .await?;
+
+    Ok(outcome)
+}
@@ -0,0 +1,47 @@
+use crate::scripts::fail_started_job;
+use jono_core::{Context, Result, current_timestamp_ms};
+use redis::AsyncCommands;
+
+/// Interface for recovering started jobs whose heartbeat has expired, e.g. after a worker crash
+pub struct Sweeper {
+    context: Context,
+}
+
+impl Sweeper {
+    pub fn with_context(context: Context) -> Self {
+        Self { context }
+    }
+
+    /// Count each expired run as a failed attempt, then requeue or dead-letter the job;
+    /// safe to run from many processes at once. A job that can't be recovered is skipped
+    /// so that it doesn't keep the rest from being recovered.
+    pub async fn sweep_expired_jobs(&self) -> Result<usize> {
+        let mut conn = self.context.get_connection().await?;
+        let keys = self.context.keys();
+        let now = current_timestamp_ms();
+
+        let expired: Vec<String> = conn
+            .zrangebyscore(keys.started_set(), "-inf", format!("({}", now))
+            .await?;
+
+        let mut recovered = 0;
+        for job_id in expired {
+            let outcome = fail_started_job(
+                &self.context,
+                &job_id,
+                None,
+                now,
+                "heartbeat_lost",
+                "Heartbeat expired before the job finished",
+            )
+            .await;
+            match outcome {
+                Ok(Some(_)) => recovered += 1,
+                Ok(None) => {}
+                Err(e) => eprintln!("Error recovering expired job {}: {}", job_id, e),
+            }
+        }
+
+        Ok(recovered)
+    }
+}
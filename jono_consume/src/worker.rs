@@ -1,6 +1,10 @@
-use jono_core::{JobMetadata, Result};
+use jono_core::{AttemptRecord, JobMetadata, Result, Retention};
 use serde_json::Value;
-use std::future::Future;
+use std::future::{Future, poll_fn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Poll, Waker};
+use std::time::Duration;
 
 pub trait Worker: Send + Sync {
     fn work<'a>(
@@ -12,6 +16,31 @@ pub trait Worker: Send + Sync {
 pub struct Workload {
     pub job_id: String,
     pub payload: Value,
+
+    /// Which attempt this is, starting from 1
+    pub attempt: u32,
+
+    /// The maximum number of attempts allowed
+    pub max_attempts: u32,
+
+    /// Priority; lower values are processed first
+    pub initial_priority: i64,
+
+    /// Who submitted the job; custom or hostname
+    pub origin: String,
+
+    /// When the job was submitted; UNIX timestamp in milliseconds
+    pub created_at: i64,
+
+    /// What made the earlier attempts fail
+    pub attempt_history: Vec<AttemptRecord>,
+
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retention: Option<Retention>,
+
+    /// Tells this run apart from later runs of the job; set when the job is claimed
+    pub(crate) claim_id: String,
+    abort_signal: AbortSignal,
 }
 
 impl Workload {
@@ -19,12 +48,104 @@ impl Workload {
         Self {
             job_id: metadata.id,
             payload: metadata.payload,
+            attempt: metadata.attempt_count + 1,
+            max_attempts: metadata.max_attempts,
+            initial_priority: metadata.initial_priority,
+            origin: metadata.origin,
+            created_at: metadata.created_at,
+            attempt_history: metadata.attempt_history,
+            timeout: metadata.timeout,
+            retention: metadata.retention,
+            claim_id: String::new(),
+            abort_signal: AbortSignal::default(),
         }
     }
+
+    /// True if failing this attempt dead-letters the job instead of retrying it
+    pub fn is_last_attempt(&self) -> bool {
+        self.attempt >= self.max_attempts
+    }
+
+    /// Signal that trips once the job is aborted while running; the work future is dropped
+    /// when the abort grace period ends, so wrap up before that
+    pub fn abort_signal(&self) -> &AbortSignal {
+        &self.abort_signal
+    }
+
+    /// Shorthand for checking the abort signal between steps of the work
+    pub fn is_aborted(&self) -> bool {
+        self.abort_signal.is_aborted()
+    }
+}
+
+/// Cancellation flag shared between the consumer and the work of a single job
+#[derive(Clone, Default)]
+pub struct AbortSignal {
+    inner: Arc<AbortSignalInner>,
+}
+
+#[derive(Default)]
+struct AbortSignalInner {
+    aborted: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl AbortSignal {
+    pub fn is_aborted(&self) -> bool {
+        self.inner.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the job has been aborted
+    pub async fn aborted(&self) {
+        poll_fn(|cx| {
+            if self.is_aborted() {
+                return Poll::Ready(());
+            }
+            let mut wakers = self.wakers();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+            drop(wakers);
+
+            // check again in case the signal tripped before the waker got registered
+            if self.is_aborted() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    pub(crate) fn trip(&self) {
+        self.inner.aborted.store(true, Ordering::SeqCst);
+        for waker in self.wakers().drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn wakers(&self) -> MutexGuard<'_, Vec<Waker>> {
+        self.inner
+            .wakers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum WorkSummary {
+    /// The job is done, with an optional result for the harvesters
     Success(Option<Value>),
+
+    /// The attempt failed; the job is retried by its retry policy until it runs out of attempts
     Failure(String),
+
+    /// Run the job again after the delay without using an attempt
+    Retry(Duration),
+
+    /// Run the job again at the given UNIX timestamp in milliseconds without using an attempt
+    Snooze(i64),
+
+    /// The job can never succeed, e.g. a malformed payload; dead-letter it right away
+    Discard(String),
 }
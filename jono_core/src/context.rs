@@ -28,6 +28,13 @@ impl Context {
         Ok(self.forum.redis_pool().get().await?)
     }
 
+    /// Open a new Redis connection outside the pool, for blocking commands that could
+    /// otherwise hold a pooled connection for as long as they wait
+    pub async fn get_dedicated_connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        let client = redis::Client::open(self.forum.redis_url())?;
+        Ok(client.get_multiplexed_async_connection().await?)
+    }
+
     /// Get the topic name
     pub fn topic(&self) -> &str {
         &self.topic
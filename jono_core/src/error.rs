@@ -9,8 +9,10 @@ pub enum JonoError {
     Redis(redis::RedisError),
     Serialization(serde_json::Error),
     JobNotFound(String),  // job id
+    JobExists(String),    // job id that is already taken
     InvalidJob(String),   // message with details what is invalid
     TooManyErrors(usize), // the number of errors
+    Timeout(String),      // job id that didn't finish in time
     MissingEnvVar(&'static str),
 }
 
@@ -47,8 +49,10 @@ impl std::fmt::Display for JonoError {
             Redis(err) => write!(f, "Redis error: {}", err),
             Serialization(err) => write!(f, "Serialization error: {}", err),
             JobNotFound(job_id) => write!(f, "Job not found: {}", job_id),
+            JobExists(job_id) => write!(f, "Job already exists: {}", job_id),
             InvalidJob(msg) => write!(f, "Invalid job: {}", msg),
             TooManyErrors(count) => write!(f, "Too many consecutive errors: {}", count),
+            Timeout(job_id) => write!(f, "Timed out waiting for job: {}", job_id),
             MissingEnvVar(var) => write!(f, "Missing environment variable: {}", var),
         }
     }
@@ -63,8 +67,10 @@ impl std::error::Error for JonoError {
             Redis(err) => Some(err),
             Serialization(err) => Some(err),
             JobNotFound(_) => None,
+            JobExists(_) => None,
             InvalidJob(_) => None,
             TooManyErrors(_) => None,
+            Timeout(_) => None,
             MissingEnvVar(_) => None,
         }
     }
@@ -4,7 +4,7 @@ use redis::AsyncCommands;
 use serde_json::Value;
 use std::collections::HashMap;
 
-use crate::{Context, JobMetadata, JobStatus, JonoError, Result};
+use crate::{Context, JobMetadata, JobStatus, JonoError, Result, TimelineEntry};
 
 /// Interface for querying job details
 pub struct Inspector {
@@ -75,6 +75,14 @@ impl Inspector {
             return Ok(JobStatus::Aborted);
         }
 
+        let in_perished_set: bool = conn
+            .zscore::<_, _, Option<i64>>(keys.perished_set(), job_id)
+            .await?
+            .is_some();
+        if in_perished_set {
+            return Ok(JobStatus::Perished);
+        }
+
         let has_completed_at_field: Option<String> = conn
             .hget(keys.job_metadata_hash(job_id), "completed_at")
             .await?;
@@ -115,10 +123,35 @@ impl Inspector {
             return Err(JonoError::JobNotFound(job_id.to_string()));
         }
 
-        let hash: HashMap<String, String> = conn.hgetall(&metadata_key).await?;
+        let mut hash: HashMap<String, String> = conn.hgetall(&metadata_key).await?;
+        drop(conn);
+
+        // jobs enqueued before the status was recorded for queued and postponed jobs
+        if !hash.contains_key("status") {
+            let status = self.get_job_status(job_id).await?;
+            hash.insert("status".to_string(), status.to_string());
+        }
+
         JobMetadata::from_hash(hash)
     }
 
+    /// Get every transition the job has made so far, oldest first
+    pub async fn get_job_timeline(&self, job_id: &str) -> Result<Vec<TimelineEntry>> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+
+        let exists: bool = conn.exists(keys.job_metadata_hash(job_id)).await?;
+        if !exists {
+            return Err(JonoError::JobNotFound(job_id.to_string()));
+        }
+
+        let entries: Vec<String> = conn.lrange(keys.job_timeline_list(job_id), 0, -1).await?;
+        entries
+            .iter()
+            .map(|entry| Ok(serde_json::from_str(entry)?))
+            .collect()
+    }
+
     /// Get the current state of jobs by ID in the Jono system, optionally filtered by criteria in JobFilter.
     pub async fn get_status_to_job_ids(&self, filter: JobFilter) -> Result<MapStatusToJobId> {
         let keys = self.context.keys();
@@ -131,6 +164,7 @@ impl Inspector {
                 JobStatus::Started,
                 JobStatus::Aborted,
                 JobStatus::Completed,
+                JobStatus::Perished,
             ],
         };
 
@@ -166,7 +200,10 @@ impl Inspector {
                     pipe.zrange(keys.completed_set(), 0, -1);
                     status_to_index.push(JobStatus::Completed);
                 }
-                JobStatus::Perished => {} // TODO: wait for the deadletter implementation
+                JobStatus::Perished => {
+                    pipe.zrange(keys.perished_set(), 0, -1);
+                    status_to_index.push(JobStatus::Perished);
+                }
             }
         }
 
@@ -185,7 +222,7 @@ impl Inspector {
                     JobStatus::Started => map.started = std::mem::take(&mut result[i]),
                     JobStatus::Aborted => map.aborted = std::mem::take(&mut result[i]),
                     JobStatus::Completed => map.completed = std::mem::take(&mut result[i]),
-                    JobStatus::Perished => {} // TODO: wait for the deadletter implementation
+                    JobStatus::Perished => map.perished = std::mem::take(&mut result[i]),
                 }
             }
         }
@@ -225,6 +262,7 @@ impl Inspector {
                 started: process(&status_to_job_ids.started).await,
                 aborted: process(&status_to_job_ids.aborted).await,
                 completed: process(&status_to_job_ids.completed).await,
+                perished: process(&status_to_job_ids.perished).await,
             });
         };
 
@@ -238,7 +276,7 @@ impl Inspector {
                 Started => result.started = process(&status_to_job_ids.started).await,
                 Aborted => result.aborted = process(&status_to_job_ids.aborted).await,
                 Completed => result.completed = process(&status_to_job_ids.completed).await,
-                Perished => {} // TODO: wait for the deadletter implementation
+                Perished => result.perished = process(&status_to_job_ids.perished).await,
             }
         }
 
@@ -268,6 +306,8 @@ pub struct MapStatusToJobId {
     pub aborted: Vec<String>,
     /// Jobs that are ready to be harvested; completed but not post-processed
     pub completed: Vec<String>,
+    /// Jobs that ran out of attempts and are waiting in the dead-letter set
+    pub perished: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -282,4 +322,6 @@ pub struct MapStatusToJobMetadata {
     pub aborted: Vec<JobMetadata>,
     /// Jobs that are ready to be harvested; completed but not post-processed
     pub completed: Vec<JobMetadata>,
+    /// Jobs that ran out of attempts and are waiting in the dead-letter set
+    pub perished: Vec<JobMetadata>,
 }
@@ -0,0 +1,280 @@
+use crate::{Context, JonoError, Result, transition_args, transition_script};
+use futures_util::Stream;
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// How long a subscriber blocks on the event stream in one read
+const EVENT_READ_BLOCK_MS: usize = 5_000;
+
+/// How many events a subscriber reads at most in one go
+const EVENT_READ_COUNT: usize = 100;
+
+/// Adds an event to the event stream of the topic and to the timeline of the job.
+///
+/// KEYS[1] = event stream, to run the script where the keys of the topic are
+/// ARGV[1] = job ID, ARGV[2] = event kind, ARGV[3] = detail or an empty string,
+/// ARGV[4..] = transition arguments
+///
+/// Returns the ID of the stream entry.
+static RECORD_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local detail = ARGV[3]
+        if detail == '' then
+            detail = nil
+        end
+        return record_event(ARGV[1], ARGV[2], detail)
+        "#,
+    )
+});
+
+/// All the lifecycle transitions that are published to the event stream of a topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEventKind {
+    /// The job was submitted by a producer.
+    Submitted,
+    /// The postponed job became due and was moved to the queue.
+    Promoted,
+    /// The job was claimed by a consumer.
+    Started,
+    /// The heartbeat of a started job expired and the attempt was counted as failed.
+    HeartbeatLost,
+    /// An attempt of the job failed.
+    Failed,
+    /// The job was put back to be run again.
+    Retried,
+    /// The job was put back to the queue unfinished by a consumer that was shutting down.
+    Released,
+    /// The perished job was moved back to the queue with a fresh attempt budget.
+    Resurrected,
+    /// The job was completed successfully.
+    Completed,
+    /// The job was canceled.
+    Aborted,
+    /// The job ran out of attempts or was discarded.
+    Perished,
+    /// The completed job was post-processed by a harvester.
+    Harvested,
+    /// Post-processing the completed job failed; it's retried or, out of reap attempts,
+    /// moved to the reap dead-letter set.
+    ReapFailed,
+    /// The job that ran out of reap attempts was made harvestable again.
+    Replayed,
+}
+
+impl Display for JobEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            JobEventKind::Submitted => "submitted",
+            JobEventKind::Promoted => "promoted",
+            JobEventKind::Started => "started",
+            JobEventKind::HeartbeatLost => "heartbeat_lost",
+            JobEventKind::Failed => "failed",
+            JobEventKind::Retried => "retried",
+            JobEventKind::Released => "released",
+            JobEventKind::Resurrected => "resurrected",
+            JobEventKind::Completed => "completed",
+            JobEventKind::Aborted => "aborted",
+            JobEventKind::Perished => "perished",
+            JobEventKind::Harvested => "harvested",
+            JobEventKind::ReapFailed => "reap_failed",
+            JobEventKind::Replayed => "replayed",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for JobEventKind {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "submitted" => Ok(JobEventKind::Submitted),
+            "promoted" => Ok(JobEventKind::Promoted),
+            "started" => Ok(JobEventKind::Started),
+            "heartbeat_lost" => Ok(JobEventKind::HeartbeatLost),
+            "failed" => Ok(JobEventKind::Failed),
+            "retried" => Ok(JobEventKind::Retried),
+            "released" => Ok(JobEventKind::Released),
+            "resurrected" => Ok(JobEventKind::Resurrected),
+            "completed" => Ok(JobEventKind::Completed),
+            "aborted" => Ok(JobEventKind::Aborted),
+            "perished" => Ok(JobEventKind::Perished),
+            "harvested" => Ok(JobEventKind::Harvested),
+            "reap_failed" => Ok(JobEventKind::ReapFailed),
+            "replayed" => Ok(JobEventKind::Replayed),
+            _ => Err(format!("Unknown job event kind: {}", s)),
+        }
+    }
+}
+
+/// A lifecycle transition of a job, as read from the event stream of a topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobEvent {
+    /// Redis stream entry ID of the event; subscribe after it to resume from where you left off
+    pub event_id: String,
+
+    /// The job that made the transition
+    pub job_id: String,
+
+    /// The topic the job belongs to
+    pub topic: String,
+
+    /// What happened to the job
+    pub kind: JobEventKind,
+
+    /// When the transition happened; UNIX timestamp in milliseconds
+    pub occurred_at: i64,
+
+    /// Who made the transition; hostname and process ID of the producer, consumer or harvester
+    pub worker: String,
+
+    /// More about the transition, e.g. the error of a failed attempt
+    pub detail: Option<String>,
+}
+
+impl JobEvent {
+    /// Publish a transition of a job to the event stream of the topic
+    /// and record it on the timeline of the job; the transitions made by Jono itself
+    /// are recorded by the transition scripts in the same atomic step as the transition
+    pub async fn publish(context: &Context, job_id: &str, kind: JobEventKind) -> Result<()> {
+        Self::publish_with_detail(context, job_id, kind, None).await
+    }
+
+    /// Publish a transition of a job like `publish`, with more about what happened
+    pub async fn publish_with_detail(
+        context: &Context,
+        job_id: &str,
+        kind: JobEventKind,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let mut conn = context.get_connection().await?;
+        let keys = context.keys();
+
+        let _: String = RECORD_SCRIPT
+            .key(keys.event_stream())
+            .arg(job_id)
+            .arg(kind.to_string())
+            .arg(detail.unwrap_or_default())
+            .arg(transition_args(context))
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Stream of the events published on the topic from now on
+    pub async fn subscribe(context: &Context) -> Result<impl Stream<Item = Result<JobEvent>>> {
+        let mut conn = context.get_connection().await?;
+
+        let latest: Vec<(String, HashMap<String, String>)> = redis::cmd("XREVRANGE")
+            .arg(context.keys().event_stream())
+            .arg("+")
+            .arg("-")
+            .arg("COUNT")
+            .arg(1)
+            .query_async(&mut conn)
+            .await?;
+        let last_event_id = match latest.into_iter().next() {
+            Some((event_id, _)) => event_id,
+            None => "0-0".to_string(),
+        };
+
+        Ok(Self::subscribe_after(context.clone(), last_event_id))
+    }
+
+    /// Stream of the events published on the topic after the given event ID;
+    /// "0-0" replays all the events still kept in the stream.
+    /// The stream blocks on its own connection so that it doesn't hold on to the shared pool.
+    pub fn subscribe_after(
+        context: Context,
+        last_event_id: impl ToString,
+    ) -> impl Stream<Item = Result<JobEvent>> {
+        let state = (context, None, last_event_id.to_string(), VecDeque::new());
+
+        stream::unfold(
+            state,
+            |(context, mut conn, mut last_event_id, mut buffer)| async move {
+                loop {
+                    if let Some(event) = buffer.pop_front() {
+                        return Some((Ok(event), (context, conn, last_event_id, buffer)));
+                    }
+
+                    let mut open_conn = match conn.take() {
+                        Some(open_conn) => open_conn,
+                        None => match context.get_dedicated_connection().await {
+                            Ok(open_conn) => open_conn,
+                            Err(e) => {
+                                return Some((Err(e), (context, None, last_event_id, buffer)));
+                            }
+                        },
+                    };
+
+                    match read_events(&mut open_conn, &context, &last_event_id).await {
+                        Ok(events) => {
+                            conn = Some(open_conn);
+                            if let Some(last) = events.last() {
+                                last_event_id = last.event_id.clone();
+                            }
+                            buffer.extend(events);
+                        }
+                        // the connection is opened again for the next read
+                        Err(e) => return Some((Err(e), (context, None, last_event_id, buffer))),
+                    }
+                }
+            },
+        )
+    }
+
+    fn from_entry(event_id: String, fields: HashMap<String, String>) -> Result<Self> {
+        let field = |name: &str| {
+            fields
+                .get(name)
+                .ok_or_else(|| JonoError::InvalidJob(format!("Missing event {} field", name)))
+        };
+
+        let kind = field("kind")?;
+        let occurred_at = field("occurred_at")?;
+
+        Ok(Self {
+            job_id: field("job_id")?.clone(),
+            topic: field("topic")?.clone(),
+            worker: field("worker")?.clone(),
+            detail: fields.get("detail").cloned(),
+            kind: kind.parse().map_err(JonoError::InvalidJob)?,
+            occurred_at: occurred_at.parse().map_err(|e| {
+                JonoError::InvalidJob(format!("Invalid occurred_at {:?}: {}", occurred_at, e))
+            })?,
+            event_id,
+        })
+    }
+}
+
+type StreamReadReply = Option<Vec<(String, Vec<(String, HashMap<String, String>)>)>>;
+
+/// Block until there are events after the given event ID or the read times out
+async fn read_events(
+    conn: &mut impl redis::aio::ConnectionLike,
+    context: &Context,
+    last_event_id: &str,
+) -> Result<Vec<JobEvent>> {
+    let reply: StreamReadReply = redis::cmd("XREAD")
+        .arg("COUNT")
+        .arg(EVENT_READ_COUNT)
+        .arg("BLOCK")
+        .arg(EVENT_READ_BLOCK_MS)
+        .arg("STREAMS")
+        .arg(context.keys().event_stream())
+        .arg(last_event_id)
+        .query_async(conn)
+        .await?;
+
+    let entries = reply.into_iter().flatten().flat_map(|(_, entries)| entries);
+    entries
+        .map(|(event_id, fields)| JobEvent::from_entry(event_id, fields))
+        .collect()
+}
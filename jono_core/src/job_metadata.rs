@@ -1,6 +1,11 @@
 use crate::error::{JonoError, Result};
+use crate::{JobStatus, Retention, RetryPolicy};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Metadata for a job in the Jono system
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +16,10 @@ pub struct JobMetadata {
     /// The job JSON payload
     pub payload: serde_json::Value,
 
+    /// Status of the job as last recorded in the metadata; `Inspector` falls back to the set
+    /// the job is in for jobs that were enqueued without a recorded status
+    pub status: JobStatus,
+
     /// The maximum number of attempts allowed
     pub max_attempts: u32,
 
@@ -21,66 +30,152 @@ pub struct JobMetadata {
     pub attempt_count: u32,
 
     // Keeping track what made attempts fail
-    pub attempt_history: Vec<serde_json::Value>,
+    pub attempt_history: Vec<AttemptRecord>,
 
     // Result on job completion from the worker
     pub work_summary: Option<serde_json::Value>,
 
     // Who submitted the job; custom or hostname
     pub origin: String,
+
+    /// Number of failed post-processing attempts by harvesters
+    pub reap_attempt_count: u32,
+
+    /// Keeping track what made post-processing attempts fail
+    pub reap_history: Vec<AttemptRecord>,
+
+    /// When the job was submitted; UNIX timestamp in milliseconds
+    pub created_at: i64,
+
+    /// When the job was last claimed by a consumer; UNIX timestamp in milliseconds
+    pub started_at: Option<i64>,
+
+    /// When the job was completed; UNIX timestamp in milliseconds
+    pub completed_at: Option<i64>,
+
+    /// When the job was aborted; UNIX timestamp in milliseconds
+    pub aborted_at: Option<i64>,
+
+    /// When the job ran out of attempts; UNIX timestamp in milliseconds
+    pub perished_at: Option<i64>,
+
+    /// How long to wait before retrying a failed attempt
+    pub retry_policy: RetryPolicy,
+
+    /// How long a single attempt may run; None to use the consumer default
+    pub timeout: Option<Duration>,
+
+    /// How long to keep the result after completion; None to use the consumer default
+    pub retention: Option<Retention>,
+}
+
+/// A failed attempt of a job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    /// Which attempt failed, starting from 1
+    pub attempt: u32,
+
+    /// What kind of failure it was, e.g. "failure", "timeout", "panic", "heartbeat_lost"
+    /// or "reap_failure"
+    pub reason: String,
+
+    /// The error message of the failure
+    pub error: String,
+
+    /// When the attempt failed; UNIX timestamp in milliseconds
+    pub failed_at: i64,
 }
 
 impl JobMetadata {
     /// Convert a Redis hash into more structured job metadata
     pub fn from_hash(hash: HashMap<String, String>) -> Result<Self> {
-        let id = hash
-            .get("id")
-            .ok_or_else(|| JonoError::InvalidJob("Missing id field".to_string()))?
-            .clone();
-
-        let payload_str = hash
-            .get("payload")
-            .ok_or_else(|| JonoError::InvalidJob("Missing payload field".to_string()))?;
-        let payload = serde_json::from_str(payload_str)
-            .map_err(|_| JonoError::InvalidJob("Invalid payload JSON".to_string()))?;
-
-        let max_attempts = hash
-            .get("max_attempts")
-            .ok_or_else(|| JonoError::InvalidJob("Missing max_attempts field".to_string()))?
-            .parse::<u32>()
-            .map_err(|_| JonoError::InvalidJob("Invalid max_attempts".to_string()))?;
-
-        let attempt_count = hash
-            .get("attempt_count")
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(0);
-
-        let initial_priority = hash
-            .get("initial_priority")
-            .ok_or_else(|| JonoError::InvalidJob("Missing initial_priority field".to_string()))?
-            .parse::<i64>()
-            .map_err(|_| JonoError::InvalidJob("Invalid initial_priority".to_string()))?;
-
-        let work_summary_str = hash
-            .get("work_summary")
-            .ok_or_else(|| JonoError::InvalidJob("Missing work_summary field".to_string()))?;
-        let work_summary = serde_json::from_str(work_summary_str)
-            .map_err(|_| JonoError::InvalidJob("Invalid work_summary JSON".to_string()))?;
-
-        let origin = hash
-            .get("origin")
-            .ok_or_else(|| JonoError::InvalidJob("Missing origin field".to_string()))?
-            .clone();
+        let id = required(&hash, "id")?.to_string();
+        let payload = parse_json(&hash, "payload")?;
+        let status = parse_value(&hash, "status")?;
+        let max_attempts = parse_value(&hash, "max_attempts")?;
+        let initial_priority = parse_value(&hash, "initial_priority")?;
+        let attempt_count = parse_optional_value(&hash, "attempt_count")?.unwrap_or(0);
+        let attempt_history = parse_json(&hash, "attempt_history")?;
+        let work_summary = parse_json(&hash, "work_summary")?;
+        let origin = required(&hash, "origin")?.to_string();
+        let reap_attempt_count = parse_optional_value(&hash, "reap_attempt_count")?.unwrap_or(0);
+        let reap_history = parse_optional_json(&hash, "reap_history")?.unwrap_or_default();
+
+        let created_at = parse_value(&hash, "created_at")?;
+        let started_at = parse_optional_value(&hash, "started_at")?;
+        let completed_at = parse_optional_value(&hash, "completed_at")?;
+        let aborted_at = parse_optional_value(&hash, "aborted_at")?;
+        let perished_at = parse_optional_value(&hash, "perished_at")?;
+
+        // jobs submitted before retry policies existed retry immediately
+        let retry_policy = parse_optional_json(&hash, "retry_policy")?.unwrap_or_default();
+        let timeout = parse_optional_value(&hash, "timeout_ms")?.map(Duration::from_millis);
+        let retention = parse_optional_json(&hash, "retention")?;
 
         Ok(Self {
             id,
             payload,
+            status,
             max_attempts,
             attempt_count,
             initial_priority,
-            attempt_history: vec![],
+            attempt_history,
             work_summary,
             origin,
+            reap_attempt_count,
+            reap_history,
+            created_at,
+            started_at,
+            completed_at,
+            aborted_at,
+            perished_at,
+            retry_policy,
+            timeout,
+            retention,
         })
     }
 }
+
+fn required<'a>(hash: &'a HashMap<String, String>, field: &str) -> Result<&'a str> {
+    hash.get(field)
+        .map(String::as_str)
+        .ok_or_else(|| JonoError::InvalidJob(format!("Missing {} field", field)))
+}
+
+fn parse_value<T>(hash: &HashMap<String, String>, field: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = required(hash, field)?;
+    value
+        .parse()
+        .map_err(|e| JonoError::InvalidJob(format!("Invalid {} {:?}: {}", field, value, e)))
+}
+
+fn parse_optional_value<T>(hash: &HashMap<String, String>, field: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match hash.contains_key(field) {
+        true => parse_value(hash, field).map(Some),
+        false => Ok(None),
+    }
+}
+
+fn parse_json<T: DeserializeOwned>(hash: &HashMap<String, String>, field: &str) -> Result<T> {
+    let value = required(hash, field)?;
+    serde_json::from_str(value)
+        .map_err(|e| JonoError::InvalidJob(format!("Invalid {} JSON: {}", field, e)))
+}
+
+fn parse_optional_json<T: DeserializeOwned>(
+    hash: &HashMap<String, String>,
+    field: &str,
+) -> Result<Option<T>> {
+    match hash.contains_key(field) {
+        true => parse_json(hash, field).map(Some),
+        false => Ok(None),
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{Context, JobMetadata, JobStatus, Result};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Final outcome of a job, for producers waiting on the job to finish; announced by the
+/// transition scripts and kept as long as the job metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", content = "detail", rename_all = "snake_case")]
+pub enum JobOutcome {
+    /// The job was completed with the work summary from the worker
+    Completed(Option<Value>),
+
+    /// The job ran out of attempts or was discarded, with the last error
+    Perished(String),
+
+    /// The job was canceled
+    Aborted,
+}
+
+impl JobOutcome {
+    /// The final outcome as recorded in the job metadata; None if the job hasn't finished
+    pub fn from_metadata(metadata: &JobMetadata) -> Option<Self> {
+        match metadata.status {
+            JobStatus::Completed => Some(Self::Completed(metadata.work_summary.clone())),
+            JobStatus::Perished => Some(Self::Perished(
+                metadata
+                    .attempt_history
+                    .last()
+                    .map(|attempt| attempt.error.clone())
+                    .unwrap_or_default(),
+            )),
+            JobStatus::Aborted => Some(Self::Aborted),
+            _ => None,
+        }
+    }
+
+    /// The announced outcome of the job, if any, without waiting
+    pub async fn peek(context: &Context, job_id: &str) -> Result<Option<Self>> {
+        let mut conn = context.get_connection().await?;
+        let outcome_key = context.keys().job_outcome_list(job_id);
+
+        let outcome_json: Option<String> = conn.lindex(&outcome_key, 0).await?;
+        match outcome_json {
+            Some(outcome_json) => Ok(Some(serde_json::from_str(&outcome_json)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use crate::JobEventKind;
+use serde::{Deserialize, Serialize};
+
+/// A transition on the timeline of a job, kept as long as the job metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    /// What happened to the job
+    pub kind: JobEventKind,
+
+    /// When the transition happened; UNIX timestamp in milliseconds
+    pub occurred_at: i64,
+
+    /// Who made the transition; hostname and process ID of the producer, consumer or harvester
+    pub worker: String,
+
+    /// More about the transition, e.g. the error of a failed attempt
+    pub detail: Option<String>,
+}
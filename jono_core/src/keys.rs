@@ -16,7 +16,50 @@ impl Keys {
 
     /// Redis key for the hash that holds job metadata
     pub fn job_metadata_hash(&self, job_id: &str) -> String {
-        format!("{}:{{{}}}:job:{}", self.prefix, self.topic, job_id)
+        format!("{}{}", self.job_metadata_hash_prefix(), job_id)
+    }
+
+    /// Prefix of the job metadata hash keys; for server-side scripts that derive keys from job IDs
+    pub fn job_metadata_hash_prefix(&self) -> String {
+        format!("{}:{{{}}}:job:", self.prefix, self.topic)
+    }
+
+    /// Redis key for the list that holds the final outcome of a job; producers waiting on
+    /// the job block on it. Expires with the job metadata.
+    pub fn job_outcome_list(&self, job_id: &str) -> String {
+        format!("{}{}", self.job_outcome_list_prefix(), job_id)
+    }
+
+    /// Prefix of the job outcome list keys; for server-side scripts that derive keys from job IDs
+    pub fn job_outcome_list_prefix(&self) -> String {
+        format!("{}:{{{}}}:outcome:", self.prefix, self.topic)
+    }
+
+    /// Redis key for the list that holds the timeline of a job; expires with the job metadata
+    pub fn job_timeline_list(&self, job_id: &str) -> String {
+        format!("{}{}", self.job_timeline_list_prefix(), job_id)
+    }
+
+    /// Prefix of the job timeline list keys; for server-side scripts that derive keys from job IDs
+    pub fn job_timeline_list_prefix(&self) -> String {
+        format!("{}:{{{}}}:timeline:", self.prefix, self.topic)
+    }
+
+    /// Redis key for the string that holds the ID of the job submitted with a deduplication key;
+    /// expires when the deduplication window ends
+    pub fn dedup_string(&self, dedup_key: &str) -> String {
+        format!("{}{}", self.dedup_string_prefix(), dedup_key)
+    }
+
+    /// Prefix of the deduplication string keys; for server-side scripts that derive keys
+    /// from deduplication keys
+    pub fn dedup_string_prefix(&self) -> String {
+        format!("{}:{{{}}}:dedup:", self.prefix, self.topic)
+    }
+
+    /// Redis key for the stream that holds the lifecycle events of all the jobs in the topic
+    pub fn event_stream(&self) -> String {
+        format!("{}:{{{}}}:events", self.prefix, self.topic)
     }
 
     /// Redis key for the sorted set that holds the postponed jobs with to-be-executed timestamps as scores
@@ -29,6 +72,12 @@ impl Keys {
         format!("{}:{{{}}}:queued", self.prefix, self.topic)
     }
 
+    /// Redis key for the list that wakes up consumers waiting for jobs,
+    /// with an entry pushed for each job put into the queue
+    pub fn queued_wake_up_list(&self) -> String {
+        format!("{}:{{{}}}:queued_wake_up", self.prefix, self.topic)
+    }
+
     /// Redis key for the sorted set that holds running jobs with heartbeat timestamps as scores
     pub fn started_set(&self) -> String {
         format!("{}:{{{}}}:started", self.prefix, self.topic)
@@ -44,4 +93,22 @@ impl Keys {
     pub fn completed_set(&self) -> String {
         format!("{}:{{{}}}:completed", self.prefix, self.topic)
     }
+
+    /// Redis key for the sorted set that holds completed jobs claimed by harvesters
+    /// with lease expiry timestamps as scores
+    pub fn harvesting_set(&self) -> String {
+        format!("{}:{{{}}}:harvesting", self.prefix, self.topic)
+    }
+
+    /// Redis key for the sorted set that holds completed jobs that ran out of reap attempts
+    /// (reap dead-letter) with time of failure timestamps as scores
+    pub fn reap_failed_set(&self) -> String {
+        format!("{}:{{{}}}:reap_failed", self.prefix, self.topic)
+    }
+
+    /// Redis key for the sorted set that holds jobs that ran out of attempts (dead-letter)
+    /// with time of death timestamps as scores
+    pub fn perished_set(&self) -> String {
+        format!("{}:{{{}}}:perished", self.prefix, self.topic)
+    }
 }
@@ -7,9 +7,15 @@ mod context;
 mod error;
 mod forum;
 mod inspector;
+mod job_event;
 mod job_metadata;
+mod job_outcome;
 mod job_status;
+mod job_timeline;
 mod keys;
+mod retention;
+mod retry_policy;
+mod transition;
 mod util;
 
 pub use context::Context;
@@ -17,11 +23,23 @@ pub use error::{JonoError, Result};
 pub use forum::Forum;
 pub use inspector::Inspector;
 pub use inspector::JobFilter;
-pub use job_metadata::JobMetadata;
+pub use job_event::{JobEvent, JobEventKind};
+pub use job_metadata::{AttemptRecord, JobMetadata};
+pub use job_outcome::JobOutcome;
 pub use job_status::JobStatus;
+pub use job_timeline::TimelineEntry;
 pub use keys::Keys;
-pub use util::{current_timestamp_ms, generate_job_id, get_hostname};
+pub use retention::Retention;
+pub use retry_policy::RetryPolicy;
+pub use transition::{transition_args, transition_script};
+pub use util::{current_timestamp_ms, generate_job_id, get_hostname, sleep};
+
+#[cfg(feature = "runtime-tokio")]
+pub use util::shutdown_signal;
 
 pub mod prelude {
-    pub use crate::{Context, Forum, Inspector, JobFilter, JobMetadata, JobStatus, JonoError};
+    pub use crate::{
+        AttemptRecord, Context, Forum, Inspector, JobEvent, JobEventKind, JobFilter, JobMetadata,
+        JobOutcome, JobStatus, JonoError, Retention, RetryPolicy, TimelineEntry,
+    };
 }
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long the result of a completed job is kept around for harvesting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Retention {
+    /// Delete the job as soon as it completes; for fire-and-forget jobs
+    Discard,
+
+    /// Keep the result and the job metadata for the given time
+    Expire { ttl_ms: u64 },
+
+    /// Keep the result and the job metadata until the job is cleaned explicitly
+    Forever,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self::expire(Duration::from_secs(24 * 60 * 60))
+    }
+}
+
+impl Retention {
+    pub fn expire(ttl: Duration) -> Self {
+        Self::Expire {
+            ttl_ms: ttl.as_millis() as u64,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::time::Duration;
+
+/// How long a failed job waits before its next attempt; saved in the job metadata
+/// so every consumer retries the job the same way
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RetryPolicy {
+    /// Back to the queue at the initial priority right away
+    #[default]
+    Immediate,
+
+    /// The same delay before every retry
+    Fixed { delay_ms: u64 },
+
+    /// The delay doubles on each retry starting from `base_ms`, capped to `max_ms`;
+    /// `jitter` is the fraction (0.0 - 1.0) of the delay that is randomized away
+    Exponential {
+        base_ms: u64,
+        max_ms: u64,
+        jitter: f64,
+    },
+
+    /// Delay for each retry in order; the last one repeats when the schedule runs out
+    Schedule { delays_ms: Vec<u64> },
+}
+
+impl RetryPolicy {
+    pub fn fixed(delay: Duration) -> Self {
+        Self::Fixed {
+            delay_ms: delay.as_millis() as u64,
+        }
+    }
+
+    pub fn exponential(base: Duration, max: Duration, jitter: f64) -> Self {
+        Self::Exponential {
+            base_ms: base.as_millis() as u64,
+            max_ms: max.as_millis() as u64,
+            jitter: jitter.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn schedule(delays: impl IntoIterator<Item = Duration>) -> Self {
+        Self::Schedule {
+            delays_ms: delays.into_iter().map(|d| d.as_millis() as u64).collect(),
+        }
+    }
+
+    /// Delay before the retry that follows the given failed attempt (1 for the first failure)
+    pub fn delay_after_attempt(&self, attempt: u32) -> Duration {
+        let retry_index = attempt.saturating_sub(1);
+        let delay_ms = match self {
+            Self::Immediate => 0,
+            Self::Fixed { delay_ms } => *delay_ms,
+            Self::Exponential {
+                base_ms,
+                max_ms,
+                jitter,
+            } => {
+                let factor = 2u64.saturating_pow(retry_index);
+                let delay_ms = base_ms.saturating_mul(factor).min(*max_ms);
+                let spread = (delay_ms as f64 * jitter.clamp(0.0, 1.0)) as u64;
+                delay_ms - random_below(spread + 1)
+            }
+            Self::Schedule { delays_ms } => delays_ms
+                .get(retry_index as usize)
+                .or(delays_ms.last())
+                .copied()
+                .unwrap_or(0),
+        };
+        Duration::from_millis(delay_ms)
+    }
+}
+
+/// Good enough randomness for spreading retries without pulling in a RNG crate
+fn random_below(bound: u64) -> u64 {
+    if bound <= 1 {
+        return 0;
+    }
+    RandomState::new().build_hasher().finish() % bound
+}
@@ -0,0 +1,106 @@
+//! Shared Lua for the server-side transition scripts of all the Jono components, so that a job
+//! changes state and records the lifecycle event of the change in one atomic step.
+
+use crate::{Context, current_timestamp_ms, get_hostname};
+use std::sync::LazyLock;
+
+/// Roughly how many events are kept in the event stream of a topic; older ones are trimmed
+const EVENT_STREAM_MAX_LEN: usize = 10_000;
+
+/// Who makes the transitions from this process; hostname and process ID, as there can be
+/// many consumers or harvesters on one host
+static WORKER_IDENTITY: LazyLock<String> =
+    LazyLock::new(|| format!("{}:{}", get_hostname(), std::process::id()));
+
+/// Prepended to every transition script. Takes the `transition_args` from the end of ARGV,
+/// so `script_argc` is the number of arguments of the script itself, and defines:
+///
+/// `record_event(job_id, kind, detail)` adds an event to the event stream of the topic and
+/// to the timeline of the job. The timeline follows the lifetime of the job metadata hash:
+/// it's deleted if the metadata is gone and expires at the same time as the metadata,
+/// so record events after the metadata has been updated. Returns the ID of the stream entry.
+///
+/// `announce_outcome(job_id, outcome_json)` wakes up the producers waiting on the job with
+/// its final outcome. The outcome expires with the job metadata; if the metadata is already
+/// gone, it's kept only long enough to wake up the producers waiting at that moment.
+/// `aborted_outcome` is `JobOutcome::Aborted` as JSON.
+///
+/// `wake_up_consumers()` wakes up a consumer waiting for jobs; call it whenever a job is put
+/// into the queue. Only the latest wake-ups are kept, so idle topics don't pile them up.
+const TRANSITION_LUA: &str = r#"
+local script_argc = #ARGV - 9
+local event_stream, event_topic, event_worker, event_time, event_max_len,
+    metadata_prefix, timeline_prefix, outcome_prefix, wake_up_list = unpack(ARGV, script_argc + 1)
+
+local orphan_outcome_ttl_ms = 1000
+local wake_up_max_len = 100
+local aborted_outcome = '{"outcome":"aborted"}'
+
+local function record_event(job_id, kind, detail)
+    local fields = { 'job_id', job_id, 'topic', event_topic, 'kind', kind,
+        'occurred_at', event_time, 'worker', event_worker }
+    local entry = { kind = kind, occurred_at = tonumber(event_time), worker = event_worker }
+    if detail then
+        fields[#fields + 1] = 'detail'
+        fields[#fields + 1] = detail
+        entry.detail = detail
+    end
+    local event_id = redis.call('XADD', event_stream, 'MAXLEN', '~', event_max_len, '*',
+        unpack(fields))
+
+    local timeline_key = timeline_prefix .. job_id
+    local ttl = redis.call('PTTL', metadata_prefix .. job_id)
+    if ttl == -2 then
+        redis.call('DEL', timeline_key)
+        return event_id
+    end
+    redis.call('RPUSH', timeline_key, cjson.encode(entry))
+    if ttl > 0 then
+        redis.call('PEXPIRE', timeline_key, ttl)
+    else
+        redis.call('PERSIST', timeline_key)
+    end
+    return event_id
+end
+
+local function announce_outcome(job_id, outcome_json)
+    local outcome_key = outcome_prefix .. job_id
+    redis.call('DEL', outcome_key)
+    redis.call('RPUSH', outcome_key, outcome_json)
+    local ttl = redis.call('PTTL', metadata_prefix .. job_id)
+    if ttl == -2 then
+        redis.call('PEXPIRE', outcome_key, orphan_outcome_ttl_ms)
+    elseif ttl > 0 then
+        redis.call('PEXPIRE', outcome_key, ttl)
+    end
+end
+
+local function wake_up_consumers()
+    redis.call('RPUSH', wake_up_list, 1)
+    redis.call('LTRIM', wake_up_list, -wake_up_max_len, -1)
+end
+"#;
+
+/// Create a script for a job state transition that records its lifecycle events with
+/// `record_event(job_id, kind, detail)`, announces final outcomes with
+/// `announce_outcome(job_id, outcome_json)` and wakes up consumers for queued jobs with
+/// `wake_up_consumers()`; invoke it with `transition_args` as the last argument
+pub fn transition_script(code: &str) -> redis::Script {
+    redis::Script::new(&format!("{}{}", TRANSITION_LUA, code))
+}
+
+/// Arguments every transition script takes after its own ones; expands to several arguments
+pub fn transition_args(context: &Context) -> Vec<String> {
+    let keys = context.keys();
+    vec![
+        keys.event_stream(),
+        context.topic().to_string(),
+        WORKER_IDENTITY.to_string(),
+        current_timestamp_ms().to_string(),
+        EVENT_STREAM_MAX_LEN.to_string(),
+        keys.job_metadata_hash_prefix(),
+        keys.job_timeline_list_prefix(),
+        keys.job_outcome_list_prefix(),
+        keys.queued_wake_up_list(),
+    ]
+}
@@ -1,4 +1,4 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use ulid::Ulid;
 
 /// Generate a new ULID for a job
@@ -14,6 +14,45 @@ pub fn current_timestamp_ms() -> i64 {
         .as_millis() as i64
 }
 
+/// Sleep without blocking the executor thread of the enabled async runtime
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "runtime-tokio")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(feature = "runtime-async-std")]
+    async_std::task::sleep(duration).await;
+}
+
+/// Wait until the process is asked to terminate with SIGINT (Ctrl+C) or SIGTERM;
+/// pass this to `run_until` or `reap_until` for a graceful shutdown
+#[cfg(feature = "runtime-tokio")]
+pub async fn shutdown_signal() {
+    use futures_util::future::select;
+    use std::pin::pin;
+
+    let interrupt = pin!(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    });
+
+    #[cfg(unix)]
+    let terminate = pin!(async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    });
+
+    #[cfg(not(unix))]
+    let terminate = pin!(std::future::pending::<()>());
+
+    select(interrupt, terminate).await;
+}
+
 pub fn get_hostname() -> String {
     let hostname = std::process::Command::new("hostname")
         .output()
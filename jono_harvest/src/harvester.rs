@@ -1,7 +1,12 @@
+use crate::scripts::{
+    ACKNOWLEDGE_SCRIPT, CLEAN_EXPIRED_SCRIPT, LEASE_SCRIPT, REAP_FAIL_SCRIPT, REPLAY_SCRIPT,
+};
 use crate::{HarvestConfig, ReapSummary, Reaper, Reapload};
+use futures_util::future::{Either, select};
 use jono_core::*;
 use redis::AsyncCommands;
-use std::thread;
+use std::pin::{Pin, pin};
+use std::time::Instant;
 
 /// Interface for post-processing completed jobs on Jono queues
 pub struct Harvester<R: Reaper> {
@@ -25,16 +30,51 @@ impl<R: Reaper> Harvester<R> {
     }
 
     pub async fn reap(&self) -> Result<()> {
+        self.reap_until(std::future::pending()).await
+    }
+
+    /// Keep reaping until the `shutdown` future resolves, then let the batch in hand finish
+    /// for up to the shutdown timeout; expired results are cleaned up every cleanup interval
+    pub async fn reap_until(&self, shutdown: impl Future<Output = ()>) -> Result<()> {
+        let mut shutdown = pin!(shutdown);
         let mut consecutive_errors = 0;
+        let mut next_cleanup = Instant::now();
 
         loop {
-            match self.reap_next_batch().await {
+            if Instant::now() >= next_cleanup {
+                if let Err(e) = self.clean_expired_completed().await {
+                    eprintln!("Error cleaning up expired completed jobs: {}", e);
+                }
+                next_cleanup = Instant::now() + self.config.get_cleanup_interval();
+            }
+
+            let batch = pin!(self.reap_next_batch());
+            let result = match select(batch, shutdown.as_mut()).await {
+                Either::Left((result, _)) => result,
+                Either::Right(((), batch)) => {
+                    let timeout = pin!(jono_core::sleep(self.config.get_shutdown_timeout()));
+                    match select(batch, timeout).await {
+                        Either::Left((Err(e), _)) => {
+                            eprintln!("Error processing harvested jobs during shutdown: {}", e);
+                        }
+                        Either::Left((Ok(_), _)) => {}
+                        Either::Right(_) => {
+                            eprintln!("Shutdown timeout reached before the batch was reaped");
+                        }
+                    }
+                    return Ok(());
+                }
+            };
+
+            match result {
                 Ok(reap_summaries) if !reap_summaries.is_empty() => {
                     consecutive_errors = 0;
                 }
                 Ok(_) => {
                     // No jobs were harvested
-                    thread::sleep(self.config.get_poll_interval());
+                    if self.idle(shutdown.as_mut()).await {
+                        return Ok(());
+                    }
                 }
                 Err(e) => {
                     consecutive_errors += 1;
@@ -43,12 +83,21 @@ impl<R: Reaper> Harvester<R> {
                     if consecutive_errors >= self.config.get_max_consecutive_errors() {
                         return Err(JonoError::TooManyErrors(consecutive_errors));
                     }
-                    thread::sleep(self.config.get_poll_interval());
+                    if self.idle(shutdown.as_mut()).await {
+                        return Ok(());
+                    }
                 }
             }
         }
     }
 
+    /// Sleep for the poll interval without blocking the executor;
+    /// true if the shutdown future resolved meanwhile
+    async fn idle(&self, shutdown: Pin<&mut impl Future<Output = ()>>) -> bool {
+        let interval = pin!(jono_core::sleep(self.config.get_poll_interval()));
+        matches!(select(interval, shutdown).await, Either::Right(_))
+    }
+
     pub async fn reap_next_batch(&self) -> Result<Vec<ReapSummary>> {
         let harvested = self.harvest(self.config.get_batch_size()).await?;
         if harvested.is_empty() {
@@ -59,47 +108,142 @@ impl<R: Reaper> Harvester<R> {
 
         for job_metadata in harvested {
             let reapload = Reapload::from_metadata(job_metadata);
-            match self.reaper.reap(&reapload).await {
-                Ok(summary) => reap_summaries.push(summary),
-                Err(e) => return Err(e),
+            // unacknowledged jobs are harvested again once their lease expires
+            let summary = self.reaper.reap(&reapload).await?;
+            match &summary {
+                ReapSummary::Success(_) => self.acknowledge(&reapload.job_id).await?,
+                ReapSummary::Failure(error_message) => {
+                    eprintln!("Job {} reap failed: {}", reapload.job_id, error_message);
+                    self.fail_reap(&reapload.job_id, error_message).await?;
+                }
             }
+            reap_summaries.push(summary);
         }
 
         Ok(reap_summaries)
     }
 
-    /// Harvest jobs that have been completed and are ready for post-processing (just-once)
+    /// Harvest jobs that have been completed and are ready for post-processing (at-least-once);
+    /// the jobs are leased to this harvester until they are acknowledged or the lease expires
     pub async fn harvest(&self, limit: usize) -> Result<Vec<JobMetadata>> {
+        let deadline = Instant::now() + self.config.get_poll_timeout();
+
+        loop {
+            let job_ids = self.lease_completed_jobs(limit).await?;
+            if !job_ids.is_empty() {
+                let inspector = Inspector::with_context(self.context.clone());
+                let mut results = Vec::with_capacity(job_ids.len());
+                for job_id in job_ids {
+                    if let Ok(metadata) = inspector.get_job_metadata(&job_id).await {
+                        results.push(metadata);
+                    }
+                }
+                return Ok(results);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(Vec::new());
+            }
+            jono_core::sleep(remaining.min(self.config.get_poll_interval())).await;
+        }
+    }
+
+    /// Mark a harvested job as post-processed so that it isn't harvested again
+    pub async fn acknowledge(&self, job_id: &str) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+        let _: bool = ACKNOWLEDGE_SCRIPT
+            .key(keys.harvesting_set())
+            .arg(job_id)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Record a failed reap and retry it, or move the job to the reap dead-letter set
+    /// once it runs out of reap attempts
+    async fn fail_reap(&self, job_id: &str, error_message: &str) -> Result<()> {
         let mut conn = self.get_connection().await?;
-        let timeout = self.config.get_poll_timeout().as_secs_f64();
         let keys = self.context.keys();
 
-        let entries: Option<(String, Vec<(String, f64)>)> = conn
-            .bzmpop_min(timeout, keys.completed_set(), limit as isize)
+        let _: Option<String> = REAP_FAIL_SCRIPT
+            .key(keys.harvesting_set())
+            .key(keys.reap_failed_set())
+            .key(keys.job_metadata_hash(job_id))
+            .key(keys.job_timeline_list(job_id))
+            .key(keys.job_outcome_list(job_id))
+            .arg(job_id)
+            .arg(current_timestamp_ms())
+            .arg(error_message)
+            .arg(self.config.get_max_reap_attempts())
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
             .await?;
 
-        let inspector = Inspector::with_context(self.context.clone());
-        let mut results = Vec::new();
+        Ok(())
+    }
 
-        if let Some((_, pairs)) = entries {
-            for (job_id, _expiry_score) in pairs {
-                if let Ok(metadata) = inspector.get_job_metadata(&job_id).await {
-                    results.push(metadata);
-                }
-            }
-        }
+    /// Jobs in the reap dead-letter set, oldest failure first
+    pub async fn get_reap_failed_job_ids(&self) -> Result<Vec<String>> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+        let job_ids: Vec<String> = conn.zrange(keys.reap_failed_set(), 0, -1).await?;
+        Ok(job_ids)
+    }
+
+    /// Make a job in the reap dead-letter set harvestable again with a fresh reap attempt budget
+    pub async fn replay_reap_failed(&self, job_id: &str) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+
+        let replayed: bool = REPLAY_SCRIPT
+            .key(keys.reap_failed_set())
+            .key(keys.harvesting_set())
+            .key(keys.job_metadata_hash(job_id))
+            .key(keys.job_outcome_list(job_id))
+            .arg(job_id)
+            .arg(current_timestamp_ms())
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(replayed)
+    }
 
-        Ok(results)
+    async fn lease_completed_jobs(&self, limit: usize) -> Result<Vec<String>> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
+        let now = current_timestamp_ms();
+        let lease_expiry = now + self.config.get_lease_timeout().as_millis() as i64;
+
+        let job_ids: Vec<String> = LEASE_SCRIPT
+            .key(keys.completed_set())
+            .key(keys.harvesting_set())
+            .arg(now)
+            .arg(lease_expiry)
+            .arg(limit)
+            .arg(keys.job_metadata_hash_prefix())
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(job_ids)
     }
 
     /// Clean up expired entries from the completed set (they weren't post-processed)
+    /// and their job metadata, timelines and outcomes in one atomic step
     pub async fn clean_expired_completed(&self) -> Result<usize> {
         let mut conn = self.get_connection().await?;
         let keys = self.context.keys();
-        let now = current_timestamp_ms();
 
-        let removed: usize = conn
-            .zrembyscore(keys.completed_set(), "-inf", (now - 1).to_string())
+        let removed: usize = CLEAN_EXPIRED_SCRIPT
+            .key(keys.completed_set())
+            .arg(current_timestamp_ms())
+            .arg(keys.job_metadata_hash_prefix())
+            .arg(keys.job_timeline_list_prefix())
+            .arg(keys.job_outcome_list_prefix())
+            .invoke_async(&mut conn)
             .await?;
 
         Ok(removed)
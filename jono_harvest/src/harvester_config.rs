@@ -5,7 +5,7 @@ pub struct HarvestConfig {
     /// How long to wait between polling for new jobs
     poll_interval: Duration,
 
-    /// How long to wait for a job to become available each poll or "blocking"
+    /// How long to keep polling for a job to become available on each `harvest`
     poll_timeout: Duration,
 
     /// Maximum number of consecutive errors before stopping
@@ -13,6 +13,18 @@ pub struct HarvestConfig {
 
     /// Maximum number of jobs to harvest in a single batch
     batch_size: usize,
+
+    /// How long to wait for the batch in hand to be reaped after a shutdown request
+    shutdown_timeout: Duration,
+
+    /// How often the reap loop cleans up completed jobs whose results expired unharvested
+    cleanup_interval: Duration,
+
+    /// How many times post-processing may fail before the job goes to the reap dead-letter set
+    max_reap_attempts: u32,
+
+    /// How long harvested jobs stay claimed before other harvesters may harvest them again
+    lease_timeout: Duration,
 }
 
 impl Default for HarvestConfig {
@@ -22,6 +34,10 @@ impl Default for HarvestConfig {
             poll_timeout: Duration::from_secs(5),
             max_consecutive_errors: 3,
             batch_size: 1,
+            shutdown_timeout: Duration::from_secs(30),
+            cleanup_interval: Duration::from_secs(60),
+            max_reap_attempts: 3,
+            lease_timeout: Duration::from_secs(60),
         }
     }
 }
@@ -62,4 +78,36 @@ impl HarvestConfig {
     pub fn get_batch_size(&self) -> usize {
         self.batch_size
     }
+
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> HarvestConfig {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+    pub fn get_shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    pub fn cleanup_interval(mut self, cleanup_interval: Duration) -> HarvestConfig {
+        self.cleanup_interval = cleanup_interval;
+        self
+    }
+    pub fn get_cleanup_interval(&self) -> Duration {
+        self.cleanup_interval
+    }
+
+    pub fn max_reap_attempts(mut self, max_reap_attempts: u32) -> HarvestConfig {
+        self.max_reap_attempts = max_reap_attempts;
+        self
+    }
+    pub fn get_max_reap_attempts(&self) -> u32 {
+        self.max_reap_attempts
+    }
+
+    pub fn lease_timeout(mut self, lease_timeout: Duration) -> HarvestConfig {
+        self.lease_timeout = lease_timeout;
+        self
+    }
+    pub fn get_lease_timeout(&self) -> Duration {
+        self.lease_timeout
+    }
 }
@@ -3,6 +3,7 @@
 mod harvester;
 mod harvester_config;
 mod reaper;
+mod scripts;
 
 pub use harvester::Harvester;
 pub use harvester_config::HarvestConfig;
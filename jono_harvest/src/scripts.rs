@@ -0,0 +1,171 @@
+//! Server-side Lua scripts for the harvest state transitions that must be atomic.
+
+use jono_core::transition_script;
+use std::sync::LazyLock;
+
+/// Leases up to a limit of harvestable jobs: first the ones whose earlier lease has expired,
+/// then the next ones from the completed set. Entries without metadata (cleaned jobs)
+/// are dropped on the way.
+///
+/// KEYS[1] = completed set, KEYS[2] = harvesting set
+/// ARGV[1] = current timestamp in milliseconds, ARGV[2] = lease expiry timestamp,
+/// ARGV[3] = maximum number of jobs to lease, ARGV[4] = job metadata hash key prefix
+///
+/// Returns the leased job IDs.
+pub(crate) static LEASE_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    redis::Script::new(
+        r#"
+        local limit = tonumber(ARGV[3])
+        local candidates = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', '(' .. ARGV[1], 'LIMIT', 0, limit)
+        if #candidates < limit then
+            local popped = redis.call('ZPOPMIN', KEYS[1], limit - #candidates)
+            for i = 1, #popped, 2 do
+                candidates[#candidates + 1] = popped[i]
+            end
+        end
+
+        local leased = {}
+        for _, job_id in ipairs(candidates) do
+            if redis.call('EXISTS', ARGV[4] .. job_id) == 1 then
+                redis.call('ZADD', KEYS[2], ARGV[2], job_id)
+                leased[#leased + 1] = job_id
+            else
+                redis.call('ZREM', KEYS[2], job_id)
+            end
+        end
+        return leased
+        "#,
+    )
+});
+
+/// Marks a leased job as post-processed so that it isn't harvested again.
+///
+/// KEYS[1] = harvesting set
+/// ARGV[1] = job ID, ARGV[2..] = transition arguments
+///
+/// Returns 1 if the job was acknowledged, 0 if it wasn't leased.
+pub(crate) static ACKNOWLEDGE_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        if redis.call('ZREM', KEYS[1], ARGV[1]) == 0 then
+            return 0
+        end
+        record_event(ARGV[1], 'harvested')
+        return 1
+        "#,
+    )
+});
+
+/// Removes completed jobs whose results expired before they were harvested,
+/// together with their metadata, timeline and outcome.
+///
+/// KEYS[1] = completed set
+/// ARGV[1] = current timestamp in milliseconds, ARGV[2] = job metadata hash key prefix,
+/// ARGV[3] = job timeline list key prefix, ARGV[4] = job outcome list key prefix
+///
+/// Returns the number of removed jobs.
+pub(crate) static CLEAN_EXPIRED_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    redis::Script::new(
+        r#"
+        local expired = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', '(' .. ARGV[1])
+        for _, job_id in ipairs(expired) do
+            redis.call('ZREM', KEYS[1], job_id)
+            redis.call('DEL', ARGV[2] .. job_id)
+            redis.call('DEL', ARGV[3] .. job_id)
+            redis.call('DEL', ARGV[4] .. job_id)
+        end
+        return #expired
+        "#,
+    )
+});
+
+/// Records a failed post-processing attempt of a leased job, then makes it harvestable again
+/// right away or moves it to the reap dead-letter set by max reap attempts. Dead-lettered jobs
+/// don't expire while waiting for a replay; the remaining TTL is stored in the metadata
+/// as `reap_failed_ttl_ms` and restored by `REPLAY_SCRIPT`.
+///
+/// KEYS[1] = harvesting set, KEYS[2] = reap failed set, KEYS[3] = job metadata hash,
+/// KEYS[4] = job timeline list, KEYS[5] = job outcome list
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds, ARGV[3] = error,
+/// ARGV[4] = maximum number of reap attempts, ARGV[5..] = transition arguments
+///
+/// Returns "retry", "reap_failed", "missing" if the job metadata is gone (the job is dropped
+/// from the harvesting set) or nil if the job wasn't leased.
+pub(crate) static REAP_FAIL_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local job_id = ARGV[1]
+        local now = tonumber(ARGV[2])
+        if not redis.call('ZSCORE', KEYS[1], job_id) then
+            return false
+        end
+        if redis.call('EXISTS', KEYS[3]) == 0 then
+            redis.call('ZREM', KEYS[1], job_id)
+            return 'missing'
+        end
+
+        local attempt_count = tonumber(redis.call('HGET', KEYS[3], 'reap_attempt_count') or '0') + 1
+        local history = cjson.decode(redis.call('HGET', KEYS[3], 'reap_history') or '[]')
+        history[#history + 1] = {
+            attempt = attempt_count,
+            reason = 'reap_failure',
+            error = ARGV[3],
+            failed_at = now,
+        }
+        redis.call('HSET', KEYS[3],
+            'reap_attempt_count', attempt_count,
+            'reap_history', cjson.encode(history))
+
+        if attempt_count < tonumber(ARGV[4]) then
+            redis.call('ZADD', KEYS[1], now, job_id)
+            record_event(job_id, 'reap_failed', ARGV[3])
+            return 'retry'
+        end
+
+        redis.call('ZREM', KEYS[1], job_id)
+        redis.call('ZADD', KEYS[2], now, job_id)
+        local ttl = redis.call('PTTL', KEYS[3])
+        if ttl > 0 then
+            redis.call('HSET', KEYS[3], 'reap_failed_ttl_ms', ttl)
+            redis.call('PERSIST', KEYS[3])
+            redis.call('PERSIST', KEYS[4])
+            redis.call('PERSIST', KEYS[5])
+        end
+        record_event(job_id, 'reap_failed', ARGV[3])
+        return 'reap_failed'
+        "#,
+    )
+});
+
+/// Moves a job from the reap dead-letter set back to harvestable with a fresh reap attempt budget,
+/// and lets it expire again with the TTL it had left when it was dead-lettered.
+///
+/// KEYS[1] = reap failed set, KEYS[2] = harvesting set, KEYS[3] = job metadata hash,
+/// KEYS[4] = job outcome list
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds,
+/// ARGV[3..] = transition arguments
+///
+/// Returns 1 if the job was replayed, 0 otherwise.
+pub(crate) static REPLAY_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local job_id = ARGV[1]
+        if redis.call('ZREM', KEYS[1], job_id) == 0 then
+            return 0
+        end
+        if redis.call('EXISTS', KEYS[3]) == 0 then
+            return 0
+        end
+        redis.call('HSET', KEYS[3], 'reap_attempt_count', 0)
+        local ttl = tonumber(redis.call('HGET', KEYS[3], 'reap_failed_ttl_ms') or '0')
+        if ttl > 0 then
+            redis.call('HDEL', KEYS[3], 'reap_failed_ttl_ms')
+            redis.call('PEXPIRE', KEYS[3], ttl)
+            redis.call('PEXPIRE', KEYS[4], ttl)
+        end
+        redis.call('ZADD', KEYS[2], ARGV[2], job_id)
+        record_event(job_id, 'replayed')
+        return 1
+        "#,
+    )
+});
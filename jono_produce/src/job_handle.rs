@@ -0,0 +1,63 @@
+use jono_core::{Context, Inspector, JobOutcome, JonoError, Result};
+use redis::{AsyncCommands, Direction};
+use std::time::Duration;
+
+/// Handle to a submitted job for awaiting its final outcome
+pub struct JobHandle {
+    context: Context,
+    job_id: String,
+}
+
+impl JobHandle {
+    pub fn new(context: Context, job_id: impl ToString) -> Self {
+        Self {
+            context,
+            job_id: job_id.to_string(),
+        }
+    }
+
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    /// Wait until the job is completed, perished or aborted; blocks on a Redis notification
+    /// instead of polling. Fails with `JonoError::Timeout` if the timeout passes first.
+    pub async fn wait(&self, timeout: Option<Duration>) -> Result<JobOutcome> {
+        if let Some(outcome) = JobOutcome::peek(&self.context, &self.job_id).await? {
+            return Ok(outcome);
+        }
+
+        // jobs that finished before anyone started waiting
+        let inspector = Inspector::with_context(self.context.clone());
+        let metadata = inspector.get_job_metadata(&self.job_id).await?;
+        if let Some(outcome) = JobOutcome::from_metadata(&metadata) {
+            return Ok(outcome);
+        }
+
+        // the wait can be long or unbounded, so don't tie up a connection of the shared pool
+        let mut conn = self.context.get_dedicated_connection().await?;
+        let outcome_key = self.context.keys().job_outcome_list(&self.job_id);
+
+        // zero would block forever
+        let timeout_secs = match timeout {
+            Some(timeout) => timeout.as_secs_f64().max(0.001),
+            None => 0.0,
+        };
+        // rotating the single-item list onto itself leaves the outcome and its TTL in place
+        // for the other waiters
+        let outcome_json: Option<String> = conn
+            .blmove(
+                &outcome_key,
+                &outcome_key,
+                Direction::Left,
+                Direction::Right,
+                timeout_secs,
+            )
+            .await?;
+
+        match outcome_json {
+            Some(outcome_json) => Ok(serde_json::from_str(&outcome_json)?),
+            None => Err(JonoError::Timeout(self.job_id.clone())),
+        }
+    }
+}
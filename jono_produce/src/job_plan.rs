@@ -1,10 +1,14 @@
-use jono_core::{JonoError, Result};
+use jono_core::{JonoError, Result, Retention, RetryPolicy};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Job plan represents **a description** of a task to be queued soon and, _hopefully_, completed.
 /// Yes, it's a builder.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobPlan {
+    /// Caller-supplied job ID; a new ULID is generated if not given
+    job_id: Option<String>,
+
     /// The job JSON payload
     payload: Option<serde_json::Value>,
 
@@ -19,18 +23,59 @@ pub struct JobPlan {
 
     /// Who submitted the job; custom or hostname
     origin: Option<String>,
+
+    /// How long to wait before retrying a failed attempt
+    retry_policy: RetryPolicy,
+
+    /// How long a single attempt may run before the consumer cancels it
+    timeout: Option<Duration>,
+
+    /// How long to keep the result after completion, overriding the consumer default
+    retention: Option<Retention>,
+
+    /// Key that makes resubmissions within the window return the existing job
+    dedup_key: Option<String>,
+
+    /// How long the deduplication key holds after the first submission
+    dedup_window: Duration,
 }
 
-impl JobPlan {
-    pub fn new() -> JobPlan {
-        JobPlan {
+impl Default for JobPlan {
+    fn default() -> Self {
+        Self {
+            job_id: None,
             payload: None,
             max_attempts: 1,
             priority: 0,
             postponed_to: 0,
             origin: None,
+            retry_policy: RetryPolicy::default(),
+            timeout: None,
+            retention: None,
+            dedup_key: None,
+            dedup_window: Duration::ZERO,
         }
     }
+}
+
+impl JobPlan {
+    pub fn new() -> JobPlan {
+        JobPlan::default()
+    }
+
+    /// Use your own ID for the job, e.g. to correlate it with your own records.
+    /// Allowed are 1-128 ASCII letters, digits and `-_.:` characters.
+    ///
+    /// Jobs with the same priority are run in the lexicographical order of their IDs.
+    /// Generated ULIDs sort by submission time, which makes the queue FIFO within a priority;
+    /// your own IDs keep that guarantee only if they sort by submission time too.
+    pub fn job_id(mut self, job_id: impl ToString) -> JobPlan {
+        self.job_id = Some(job_id.to_string());
+        self
+    }
+    pub fn get_job_id(&self) -> Option<&str> {
+        self.job_id.as_deref()
+    }
 
     pub fn payload(mut self, payload: serde_json::Value) -> JobPlan {
         self.payload = Some(payload);
@@ -72,10 +117,87 @@ impl JobPlan {
         self.origin.as_deref()
     }
 
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> JobPlan {
+        self.retry_policy = retry_policy;
+        self
+    }
+    pub fn get_retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> JobPlan {
+        self.timeout = Some(timeout);
+        self
+    }
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn retention(mut self, retention: Retention) -> JobPlan {
+        self.retention = Some(retention);
+        self
+    }
+    pub fn get_retention(&self) -> Option<Retention> {
+        self.retention
+    }
+
+    /// Make the submission idempotent: submitting a job with the same key again within
+    /// the window returns the ID of the first job instead of enqueuing a duplicate,
+    /// even if that job has already finished; cleaning the job releases the key
+    pub fn dedup_key(mut self, dedup_key: impl ToString, window: Duration) -> JobPlan {
+        self.dedup_key = Some(dedup_key.to_string());
+        self.dedup_window = window;
+        self
+    }
+    pub fn get_dedup_key(&self) -> Option<&str> {
+        self.dedup_key.as_deref()
+    }
+    pub fn get_dedup_window(&self) -> Duration {
+        self.dedup_window
+    }
+
     pub async fn submit(self, producer: &crate::Producer) -> Result<String> {
         if self.payload.is_none() {
             return Err(JonoError::InvalidJob("Job payload is required".to_string()));
         }
+        if let Some(job_id) = &self.job_id {
+            validate_job_id(job_id)?;
+        }
+        if let Some(dedup_key) = &self.dedup_key {
+            if dedup_key.is_empty() {
+                return Err(JonoError::InvalidJob(
+                    "Dedup key can't be empty".to_string(),
+                ));
+            }
+            if self.dedup_window.as_millis() == 0 {
+                return Err(JonoError::InvalidJob(
+                    "Dedup window must be at least 1 ms".to_string(),
+                ));
+            }
+        }
         producer.submit_job(self).await
     }
+
+    /// Submit the job and get a handle for awaiting its outcome
+    pub async fn submit_for_handle(self, producer: &crate::Producer) -> Result<crate::JobHandle> {
+        let job_id = self.submit(producer).await?;
+        Ok(producer.get_job_handle(job_id))
+    }
+}
+
+fn validate_job_id(job_id: &str) -> Result<()> {
+    if job_id.is_empty() || job_id.len() > 128 {
+        return Err(JonoError::InvalidJob(format!(
+            "Job ID must be 1-128 characters long, got {}",
+            job_id.len()
+        )));
+    }
+    let is_allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':');
+    if let Some(c) = job_id.chars().find(|c| !is_allowed(*c)) {
+        return Err(JonoError::InvalidJob(format!(
+            "Job ID {:?} has a disallowed character {:?}",
+            job_id, c
+        )));
+    }
+    Ok(())
 }
@@ -2,12 +2,15 @@
 //!
 //! This crate allows users to submit jobs to the queue, cancel jobs, and set job priorities.
 
+mod job_handle;
 mod job_plan;
 mod producer;
+mod scripts;
 
+pub use job_handle::JobHandle;
 pub use job_plan::JobPlan;
 pub use producer::Producer;
 
 pub mod prelude {
-    pub use crate::{JobPlan, Producer};
+    pub use crate::{JobHandle, JobPlan, Producer};
 }
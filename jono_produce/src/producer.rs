@@ -1,6 +1,6 @@
-use crate::JobPlan;
+use crate::scripts::{ABORT_SCRIPT, CLEAN_SCRIPT, RESURRECT_SCRIPT, SUBMIT_SCRIPT};
+use crate::{JobHandle, JobPlan};
 use jono_core::*;
-use redis::AsyncCommands;
 use tracing::info;
 
 /// Interface for submitting jobs to Jono queues
@@ -19,7 +19,10 @@ impl Producer {
         let keys = self.context.keys();
         let now = current_timestamp_ms();
 
-        let job_id = generate_job_id();
+        let job_id = match job_plan.get_job_id() {
+            Some(job_id) => job_id.to_string(),
+            None => generate_job_id(),
+        };
         let metadata_key = keys.job_metadata_hash(&job_id);
 
         let origin = job_plan
@@ -27,47 +30,89 @@ impl Producer {
             .map(ToString::to_string)
             .unwrap_or_else(get_hostname);
 
-        let _: () = redis::pipe()
-            .atomic()
-            .hset(&metadata_key, "id", &job_id)
-            .hset(
-                &metadata_key,
-                "payload",
-                serde_json::to_string(&job_plan.get_payload())?,
-            )
-            .hset(
-                &metadata_key,
-                "max_attempts",
-                job_plan.get_max_attempts().to_string(),
-            )
-            .hset(
-                &metadata_key,
-                "initial_priority",
-                job_plan.get_priority().to_string(),
-            )
-            .hset(&metadata_key, "created_at", now.to_string())
-            .hset(&metadata_key, "attempt_history", "[]")
-            .hset(&metadata_key, "work_summary", "null")
-            .hset(&metadata_key, "origin", origin)
-            .query_async(&mut conn)
-            .await?;
-
         let postponed_to = job_plan.get_postponed_to();
-        if postponed_to > 0 && postponed_to > now {
-            let _: () = conn
-                .zadd::<_, _, _, ()>(keys.postponed_set(), &job_id, postponed_to)
-                .await?;
+        let is_postponed = postponed_to > 0 && postponed_to > now;
+        let status = match is_postponed {
+            true => JobStatus::Postponed,
+            false => JobStatus::Queued,
+        };
+
+        let mut fields = vec![
+            ("id", job_id.clone()),
+            ("status", status.to_string()),
+            ("payload", serde_json::to_string(&job_plan.get_payload())?),
+            ("max_attempts", job_plan.get_max_attempts().to_string()),
+            ("initial_priority", job_plan.get_priority().to_string()),
+            ("created_at", now.to_string()),
+            ("attempt_history", "[]".to_string()),
+            ("work_summary", "null".to_string()),
+            ("origin", origin),
+            (
+                "retry_policy",
+                serde_json::to_string(job_plan.get_retry_policy())?,
+            ),
+        ];
+        if let Some(timeout) = job_plan.get_timeout() {
+            fields.push(("timeout_ms", timeout.as_millis().to_string()));
+        }
+        if let Some(retention) = job_plan.get_retention() {
+            fields.push(("retention", serde_json::to_string(&retention)?));
+        }
+        if let Some(dedup_key) = job_plan.get_dedup_key() {
+            fields.push(("dedup_key", dedup_key.to_string()));
+        }
+
+        let (set_key, score) = match is_postponed {
+            true => (keys.postponed_set(), postponed_to),
+            false => (keys.queued_set(), job_plan.get_priority()),
+        };
+
+        let mut invocation = SUBMIT_SCRIPT.prepare_invoke();
+        invocation
+            .key(set_key)
+            .key(&metadata_key)
+            .key(keys.job_outcome_list(&job_id))
+            .key(keys.postponed_set())
+            .key(keys.queued_set())
+            .key(keys.started_set())
+            .key(keys.aborted_set())
+            .key(keys.completed_set())
+            .key(keys.harvesting_set())
+            .key(keys.reap_failed_set())
+            .key(keys.perished_set());
+        if let Some(dedup_key) = job_plan.get_dedup_key() {
+            invocation.key(keys.dedup_string(dedup_key));
+        }
+        let (outcome, submitted_id): (String, String) = invocation
+            .arg(&job_id)
+            .arg(score)
+            .arg(job_plan.get_dedup_window().as_millis() as u64)
+            .arg(fields)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+        drop(conn);
+
+        match outcome.as_str() {
+            "duplicate" => {
+                info!(
+                    job_id = %submitted_id,
+                    dedup_key = %job_plan.get_dedup_key().unwrap_or_default(),
+                    "Duplicate submission, returning the existing job"
+                );
+                return Ok(submitted_id);
+            }
+            "exists" => return Err(JonoError::JobExists(submitted_id)),
+            _ => {}
+        }
 
+        if is_postponed {
             info!(
                 job_id = %job_id,
                 postponed_to = %job_plan.get_postponed_to(),
                 "Job postponed for later execution"
             );
         } else {
-            let _: () = conn
-                .zadd::<_, _, _, ()>(keys.queued_set(), &job_id, job_plan.get_priority())
-                .await?;
-
             info!(
                 job_id = %job_id,
                 priority = %job_plan.get_priority(),
@@ -78,72 +123,108 @@ impl Producer {
         Ok(job_id)
     }
 
-    /// Cancel a job if it hasn't started processing yet
+    /// Cancel a job; a running job gets its abort signal tripped and is stopped by its consumer
+    /// once the grace period ends
     pub async fn abort_job(&self, job_id: &str, grace_period_ms: i64) -> Result<bool> {
         let mut conn = self.get_connection().await?;
         let now = current_timestamp_ms();
         let keys = self.context.keys();
-        let metadata_key = keys.job_metadata_hash(job_id);
-
-        let exists: bool = conn.exists(metadata_key).await?;
-        if !exists {
-            return Err(JonoError::JobNotFound(job_id.to_string()));
+        let grace_end = now + grace_period_ms;
+
+        let outcome: Option<String> = ABORT_SCRIPT
+            .key(keys.postponed_set())
+            .key(keys.queued_set())
+            .key(keys.started_set())
+            .key(keys.aborted_set())
+            .key(keys.job_metadata_hash(job_id))
+            .arg(job_id)
+            .arg(now)
+            .arg(grace_end)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
+        drop(conn);
+
+        match outcome.as_deref() {
+            Some("missing") => Err(JonoError::JobNotFound(job_id.to_string())),
+            Some("signaled") => {
+                info!(
+                    job_id = %job_id,
+                    grace_end = %grace_end,
+                    "Running job marked for cancellation with grace period"
+                );
+                Ok(true)
+            }
+            Some("aborted") => {
+                info!(job_id = %job_id, "Job canceled successfully");
+                Ok(true)
+            }
+            _ => {
+                info!(job_id = %job_id, "Job not found in any active queue");
+                Ok(false)
+            }
         }
+    }
 
-        #[rustfmt::skip]
-        let (removed_from_postponed, removed_from_queued, last_heartbeat): (u32, u32, Option<i64>) =
-            redis::pipe()
-                .atomic()
-                .zrem(keys.postponed_set(), job_id)
-                .zrem(keys.queued_set(), job_id)
-                .zscore(keys.started_set(), job_id)
-                .query_async(&mut conn)
-                .await?;
-
-        if last_heartbeat.is_some() {
-            let grace_end = now + grace_period_ms;
-            let _: () = conn
-                .zadd::<_, _, _, ()>(keys.aborted_set(), job_id, grace_end)
-                .await?;
-            info!(
-                job_id = %job_id,
-                grace_end = %grace_end,
-                "Running job marked for cancellation with grace period"
-            );
-            return Ok(true);
-        }
+    /// Move a perished job from the dead-letter set back to the queue with a fresh attempt budget
+    pub async fn resurrect_job(&self, job_id: &str) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+        let keys = self.context.keys();
 
-        if removed_from_queued > 0 || removed_from_postponed > 0 {
-            let _: () = conn
-                .zadd::<_, _, _, ()>(keys.aborted_set(), job_id, now)
-                .await?;
-            info!(job_id = %job_id, "Job canceled successfully");
-            return Ok(true);
-        }
+        let outcome: Option<String> = RESURRECT_SCRIPT
+            .key(keys.perished_set())
+            .key(keys.queued_set())
+            .key(keys.job_metadata_hash(job_id))
+            .key(keys.job_outcome_list(job_id))
+            .arg(job_id)
+            .arg(transition_args(&self.context))
+            .invoke_async(&mut conn)
+            .await?;
 
-        info!(job_id = %job_id, "Job not found in any active queue");
-        Ok(false)
+        match outcome.as_deref() {
+            Some("missing") => Err(JonoError::JobNotFound(job_id.to_string())),
+            Some("resurrected") => {
+                info!(job_id = %job_id, "Perished job resurrected back to queue");
+                Ok(true)
+            }
+            _ => {
+                info!(job_id = %job_id, "Job not found in the dead-letter set");
+                Ok(false)
+            }
+        }
     }
 
+    /// Delete the job and everything about it, and release its deduplication key;
+    /// returns false if the job didn't exist
     pub async fn clean_job(&self, job_id: &str) -> Result<bool> {
         let mut conn = self.get_connection().await?;
         let keys = self.context.keys();
 
-        #[rustfmt::skip]
-        let (metadata_deleted,): (u32,) = redis::pipe()
-            .atomic()
-            .zrem(keys.postponed_set(), job_id).ignore()
-            .zrem(keys.queued_set(), job_id).ignore()
-            .zrem(keys.started_set(), job_id).ignore()
-            .zrem(keys.aborted_set(), job_id).ignore()
-            .zrem(keys.completed_set(), job_id).ignore()
-            .del(keys.job_metadata_hash(job_id))
-            .query_async(&mut conn)
+        let metadata_deleted: u32 = CLEAN_SCRIPT
+            .key(keys.postponed_set())
+            .key(keys.queued_set())
+            .key(keys.started_set())
+            .key(keys.aborted_set())
+            .key(keys.completed_set())
+            .key(keys.harvesting_set())
+            .key(keys.reap_failed_set())
+            .key(keys.perished_set())
+            .key(keys.job_outcome_list(job_id))
+            .key(keys.job_timeline_list(job_id))
+            .key(keys.job_metadata_hash(job_id))
+            .arg(job_id)
+            .arg(keys.dedup_string_prefix())
+            .invoke_async(&mut conn)
             .await?;
 
         Ok(metadata_deleted > 0)
     }
 
+    /// Get a handle for awaiting the outcome of a submitted job
+    pub fn get_job_handle(&self, job_id: impl ToString) -> JobHandle {
+        JobHandle::new(self.context.clone(), job_id)
+    }
+
     async fn get_connection(&self) -> Result<impl redis::aio::ConnectionLike> {
         self.context.get_connection().await
     }
@@ -0,0 +1,145 @@
+//! Server-side Lua scripts for the submit transitions that must be atomic.
+
+use jono_core::transition_script;
+use std::sync::LazyLock;
+
+/// Creates the job metadata and enqueues the job, unless a job was submitted earlier with the same
+/// deduplication key within the deduplication window, even if it's already gone, or the job ID
+/// is taken.
+/// An ID is taken while it has metadata or is in any of the job sets, as completed jobs stay
+/// in their set after the metadata expires until the next cleanup.
+/// The outcome of an earlier job with the same ID is cleared so waiters don't get it.
+///
+/// KEYS[1] = queued or postponed set, KEYS[2] = job metadata hash, KEYS[3] = job outcome list,
+/// KEYS[4..11] = every job set, KEYS[12] = deduplication key (optional)
+/// ARGV[1] = job ID, ARGV[2] = score in the set, ARGV[3] = deduplication window in milliseconds,
+/// ARGV[4..] = job metadata field-value pairs, then the transition arguments
+///
+/// Returns "submitted", "duplicate" or "exists", and the ID of the new or existing job.
+pub(crate) static SUBMIT_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local job_id = ARGV[1]
+        if KEYS[12] then
+            local existing_id = redis.call('GET', KEYS[12])
+            if existing_id then
+                return { 'duplicate', existing_id }
+            end
+        end
+        if redis.call('EXISTS', KEYS[2]) == 1 then
+            return { 'exists', job_id }
+        end
+        for i = 4, 11 do
+            if redis.call('ZSCORE', KEYS[i], job_id) then
+                return { 'exists', job_id }
+            end
+        end
+
+        redis.call('DEL', KEYS[3])
+        redis.call('HSET', KEYS[2], unpack(ARGV, 4, script_argc))
+        redis.call('ZADD', KEYS[1], ARGV[2], job_id)
+        if KEYS[12] then
+            redis.call('SET', KEYS[12], job_id, 'PX', ARGV[3])
+        end
+        record_event(job_id, 'submitted')
+        if redis.call('HGET', KEYS[2], 'status') == 'queued' then
+            wake_up_consumers()
+        end
+        return { 'submitted', job_id }
+        "#,
+    )
+});
+
+/// Cancels a job: a started job gets an abort grace period for its consumer to act on,
+/// a queued or postponed job is taken out of its set and marked as aborted right away.
+///
+/// KEYS[1] = postponed set, KEYS[2] = queued set, KEYS[3] = started set, KEYS[4] = aborted set,
+/// KEYS[5] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = current timestamp in milliseconds,
+/// ARGV[3] = abort grace period end timestamp, ARGV[4..] = transition arguments
+///
+/// Returns "missing", "signaled", "aborted" or nil if the job isn't queued, postponed or started.
+pub(crate) static ABORT_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local job_id = ARGV[1]
+        if redis.call('EXISTS', KEYS[5]) == 0 then
+            return 'missing'
+        end
+        if redis.call('ZSCORE', KEYS[3], job_id) then
+            redis.call('ZADD', KEYS[4], ARGV[3], job_id)
+            return 'signaled'
+        end
+
+        local removed = redis.call('ZREM', KEYS[1], job_id) + redis.call('ZREM', KEYS[2], job_id)
+        if removed == 0 then
+            return false
+        end
+        redis.call('ZADD', KEYS[4], ARGV[2], job_id)
+        redis.call('HSET', KEYS[5], 'status', 'aborted', 'aborted_at', ARGV[2])
+        record_event(job_id, 'aborted')
+        announce_outcome(job_id, aborted_outcome)
+        return 'aborted'
+        "#,
+    )
+});
+
+/// Moves a perished job from the dead-letter set back to the queue at its initial priority
+/// with a fresh attempt budget; the outcome of the perished run is cleared so waiters don't get it.
+///
+/// KEYS[1] = perished set, KEYS[2] = queued set, KEYS[3] = job metadata hash,
+/// KEYS[4] = job outcome list
+/// ARGV[1] = job ID, ARGV[2..] = transition arguments
+///
+/// Returns "resurrected", "missing" or nil if the job hasn't perished.
+pub(crate) static RESURRECT_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    transition_script(
+        r#"
+        local job_id = ARGV[1]
+        if redis.call('EXISTS', KEYS[3]) == 0 then
+            return 'missing'
+        end
+        if redis.call('ZREM', KEYS[1], job_id) == 0 then
+            return false
+        end
+
+        local priority = redis.call('HGET', KEYS[3], 'initial_priority') or '0'
+        redis.call('HSET', KEYS[3], 'attempt_count', 0, 'status', 'queued')
+        redis.call('HDEL', KEYS[3], 'perished_at')
+        redis.call('DEL', KEYS[4])
+        redis.call('ZADD', KEYS[2], priority, job_id)
+        record_event(job_id, 'resurrected')
+        wake_up_consumers()
+        return 'resurrected'
+        "#,
+    )
+});
+
+/// Removes a job from every job set and deletes its metadata, timeline and outcome.
+/// The deduplication key of the job is released too, unless a later job has taken it.
+///
+/// KEYS[1..8] = every job set, KEYS[9] = job outcome list, KEYS[10] = job timeline list,
+/// KEYS[11] = job metadata hash
+/// ARGV[1] = job ID, ARGV[2] = deduplication string key prefix
+///
+/// Returns 1 if the job metadata was deleted, 0 otherwise.
+pub(crate) static CLEAN_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    redis::Script::new(
+        r#"
+        local job_id = ARGV[1]
+        local dedup_key = redis.call('HGET', KEYS[11], 'dedup_key')
+        if dedup_key then
+            local dedup_string = ARGV[2] .. dedup_key
+            if redis.call('GET', dedup_string) == job_id then
+                redis.call('DEL', dedup_string)
+            end
+        end
+
+        for i = 1, 8 do
+            redis.call('ZREM', KEYS[i], job_id)
+        end
+        redis.call('DEL', KEYS[9], KEYS[10])
+        return redis.call('DEL', KEYS[11])
+        "#,
+    )
+});